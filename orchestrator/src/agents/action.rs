@@ -1,46 +1,265 @@
-// Action Agent: Executes approved actions on external services
+// Action Agent: executes approved actions on external services through a
+// pluggable `Connector` per target, each one authenticating with its own
+// credentials, signing the outbound request so a receiving webhook can
+// verify it actually came from this orchestrator, and attaching a
+// deterministic idempotency key so a retried execution can't double-post a
+// Slack message or create a duplicate JIRA ticket.
 
-use serde_json::Value;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::smtp::client::{Tls, TlsParameters};
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
 use tracing::info;
 use uuid::Uuid;
 
-pub struct ActionAgent;
+type HmacSha256 = Hmac<Sha256>;
+
+#[async_trait]
+pub trait Connector: Send + Sync {
+    /// Executes `payload` against this connector's target service.
+    /// `idempotency_key` is stable across retries of the same action so the
+    /// connector (or the service on the other end) can dedupe a resend.
+    async fn execute(&self, payload: &Value, idempotency_key: &str) -> Result<Value, String>;
+}
+
+pub struct ActionAgent {
+    connectors: HashMap<String, Arc<dyn Connector>>,
+}
 
 impl ActionAgent {
+    /// Builds one connector per action type from env-configured credentials,
+    /// mirroring `LlmRegistry`'s "one entry per backend, built once at
+    /// construction" shape.
     pub fn new() -> Self {
-        Self
+        let signing_key = env::var("CONNECTOR_SIGNING_KEY").unwrap_or_else(|_| "dev-signing-key-change-in-production".to_string());
+
+        let mut connectors: HashMap<String, Arc<dyn Connector>> = HashMap::new();
+        connectors.insert(
+            "JIRA_TICKET".to_string(),
+            Arc::new(JiraConnector {
+                domain: env::var("JIRA_DOMAIN").unwrap_or_default(),
+                user: env::var("JIRA_USER").unwrap_or_default(),
+                token: env::var("JIRA_TOKEN").unwrap_or_default(),
+                project_key: env::var("JIRA_PROJECT_KEY").unwrap_or_else(|_| "KAN".to_string()),
+                signing_key: signing_key.clone(),
+            }),
+        );
+        connectors.insert(
+            "SLACK_ALERT".to_string(),
+            Arc::new(SlackConnector {
+                webhook_url: env::var("SLACK_WEBHOOK_URL").unwrap_or_default(),
+                signing_key: signing_key.clone(),
+            }),
+        );
+        connectors.insert(
+            "EMAIL_ALERT".to_string(),
+            Arc::new(EmailConnector {
+                smtp_host: env::var("SMTP_HOST").unwrap_or_else(|_| "smtp.gmail.com".to_string()),
+                smtp_user: env::var("SMTP_USER").unwrap_or_default(),
+                smtp_pass: env::var("SMTP_PASS").unwrap_or_default(),
+                signing_key,
+            }),
+        );
+
+        Self { connectors }
     }
 
-    pub async fn execute(
-        &self,
-        action_id: Uuid,
-        action_type: &str,
-        target_service: &str,
-        payload: &Value,
-    ) -> Result<Value> {
-        info!("Action: Executing {} on {}", action_type, target_service);
-        
-        match target_service {
-            "jira" => self.execute_jira_action(action_type, payload).await,
-            "slack" => self.execute_slack_action(action_type, payload).await,
-            "email" => self.execute_email_action(action_type, payload).await,
-            _ => Err(anyhow::anyhow!("Unknown service: {}", target_service)),
-        }
+    pub async fn execute(&self, action_id: Uuid, action_type: &str, payload: &Value) -> Result<Value> {
+        info!("Action: Executing {} (action {})", action_type, action_id);
+
+        let connector = self
+            .connectors
+            .get(action_type)
+            .ok_or_else(|| anyhow!("no connector registered for action type '{}'", action_type))?;
+
+        let idempotency_key = idempotency_key_for(action_id);
+        connector
+            .execute(payload, &idempotency_key)
+            .await
+            .map_err(|e| anyhow!(e))
     }
+}
+
+/// Deterministic per-action idempotency key: the same `action_id` always
+/// produces the same key, so every retry of the same action presents the
+/// same key to the downstream service instead of a fresh one each attempt.
+fn idempotency_key_for(action_id: Uuid) -> String {
+    let mut mac = HmacSha256::new_from_slice(b"idempotency-key")
+        .expect("HMAC accepts any key length");
+    mac.update(action_id.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Signs `(method, path, body, timestamp)` with the connector's service key
+/// so a receiving webhook can recompute the same HMAC and verify the
+/// request actually originated from this orchestrator (and wasn't replayed
+/// past `timestamp`'s freshness window).
+fn sign_request(signing_key: &str, method: &str, path: &str, body: &str, timestamp: i64) -> String {
+    let canonical = format!("{}\n{}\n{}\n{}", method, path, body, timestamp);
+    let mut mac = HmacSha256::new_from_slice(signing_key.as_bytes())
+        .expect("HMAC accepts any key length");
+    mac.update(canonical.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+struct JiraConnector {
+    domain: String,
+    user: String,
+    token: String,
+    project_key: String,
+    signing_key: String,
+}
+
+#[async_trait]
+impl Connector for JiraConnector {
+    async fn execute(&self, payload: &Value, idempotency_key: &str) -> Result<Value, String> {
+        if self.domain.is_empty() {
+            return Err("Jira credentials missing".to_string());
+        }
+
+        let summary = payload["description"].as_str().unwrap_or("AI Generated Ticket");
+        let path = "/rest/api/3/issue";
+        let body = json!({
+            "fields": {
+                "project": { "key": self.project_key },
+                "summary": summary,
+                "description": {
+                    "type": "doc", "version": 1,
+                    "content": [{ "type": "paragraph", "content": [{ "type": "text", "text": format!("Auto-created by AI.\n\n{}", summary) }] }]
+                },
+                "issuetype": { "name": "Task" }
+            }
+        });
+        let body_str = body.to_string();
+        let timestamp = Utc::now().timestamp();
+        let signature = sign_request(&self.signing_key, "POST", path, &body_str, timestamp);
+
+        let client = reqwest::Client::new();
+        let resp = client
+            .post(format!("{}{}", self.domain, path))
+            .basic_auth(&self.user, Some(&self.token))
+            .header("Idempotency-Key", idempotency_key)
+            .header("X-Signature", &signature)
+            .header("X-Signature-Timestamp", timestamp.to_string())
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
 
-    async fn execute_jira_action(&self, action_type: &str, payload: &Value) -> Result<Value> {
-        // TODO: Implement JIRA connector
-        Ok(serde_json::json!({"status": "stub"}))
+        if resp.status().is_success() {
+            let created: Value = resp.json().await.unwrap_or(json!({}));
+            info!("Jira ticket created");
+            Ok(json!({"connector": "jira", "response": created, "idempotency_key": idempotency_key}))
+        } else {
+            let error_text = resp.text().await.unwrap_or_default();
+            Err(format!("Jira API error: {}", error_text))
+        }
     }
+}
+
+struct SlackConnector {
+    webhook_url: String,
+    signing_key: String,
+}
+
+#[async_trait]
+impl Connector for SlackConnector {
+    async fn execute(&self, payload: &Value, idempotency_key: &str) -> Result<Value, String> {
+        if self.webhook_url.is_empty() {
+            return Err("SLACK_WEBHOOK_URL is not set".to_string());
+        }
+
+        let description = payload["description"].as_str().unwrap_or("Alert from Agentic AI");
+        let body = json!({
+            "text": format!("🔔 *Action Approved*\n\n*Action:* Slack Alert\n*Details:* {}", description),
+            "mrkdwn": true,
+        });
+        let body_str = body.to_string();
+        let timestamp = Utc::now().timestamp();
+        let signature = sign_request(&self.signing_key, "POST", "/", &body_str, timestamp);
+
+        let client = reqwest::Client::new();
+        let resp = client
+            .post(&self.webhook_url)
+            .header("Idempotency-Key", idempotency_key)
+            .header("X-Signature", &signature)
+            .header("X-Signature-Timestamp", timestamp.to_string())
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
 
-    async fn execute_slack_action(&self, action_type: &str, payload: &Value) -> Result<Value> {
-        // TODO: Implement Slack connector
-        Ok(serde_json::json!({"status": "stub"}))
+        if resp.status().is_success() {
+            Ok(json!({"connector": "slack", "status": resp.status().as_u16(), "idempotency_key": idempotency_key}))
+        } else {
+            Err(format!("Slack API error: {}", resp.status()))
+        }
     }
+}
+
+struct EmailConnector {
+    smtp_host: String,
+    smtp_user: String,
+    smtp_pass: String,
+    signing_key: String,
+}
+
+#[async_trait]
+impl Connector for EmailConnector {
+    async fn execute(&self, payload: &Value, idempotency_key: &str) -> Result<Value, String> {
+        if self.smtp_user.is_empty() || self.smtp_pass.is_empty() {
+            return Err("SMTP creds missing".to_string());
+        }
+
+        let description = payload["description"].as_str().unwrap_or("No description");
+        let mut recipient = payload["recipient"].as_str().unwrap_or("admin@example.com");
+        if recipient == "admin@example.com" {
+            recipient = &self.smtp_user;
+        }
+
+        // SMTP has no request line to sign the way an HTTP webhook call
+        // does, so the signature is computed over the same canonical shape
+        // (method "SMTP", the recipient as the "path", the body, and a
+        // timestamp) and carried in a trailing `-- signature:` line of the
+        // email body instead of an HTTP header, giving a receiving mail
+        // filter the same origin-verification hook.
+        let timestamp = Utc::now().timestamp();
+        let signature = sign_request(&self.signing_key, "SMTP", recipient, description, timestamp);
+
+        // A deterministic Message-ID derived from the idempotency key means
+        // a retried send carries the same Message-ID every time, so most
+        // receiving MTAs will dedupe it even if this connector re-sends.
+        let email = Message::builder()
+            .from(format!("Agentic AI <{}>", self.smtp_user).parse::<Mailbox>().unwrap())
+            .to(recipient.parse::<Mailbox>().map_err(|e| e.to_string())?)
+            .message_id(Some(format!("<{}@agentic-ai>", idempotency_key)))
+            .header(lettre::message::header::ContentType::TEXT_PLAIN)
+            .subject("🚨 Agentic AI Alert")
+            .body(format!(
+                "Action executed:\n\n{}\n\n-- signature: {} (ts: {})",
+                description, signature, timestamp
+            ))
+            .map_err(|e| e.to_string())?;
+
+        let creds = Credentials::new(self.smtp_user.clone(), self.smtp_pass.clone());
+        let tls_parameters = TlsParameters::new(self.smtp_host.clone()).map_err(|e| e.to_string())?;
+        let mailer: AsyncSmtpTransport<Tokio1Executor> = AsyncSmtpTransport::<Tokio1Executor>::relay(&self.smtp_host)
+            .map_err(|e| e.to_string())?
+            .port(465)
+            .tls(Tls::Wrapper(tls_parameters))
+            .credentials(creds)
+            .build();
 
-    async fn execute_email_action(&self, action_type: &str, payload: &Value) -> Result<Value> {
-        // TODO: Implement Email connector
-        Ok(serde_json::json!({"status": "stub"}))
+        mailer.send(email).await.map_err(|e| e.to_string())?;
+        Ok(json!({"connector": "email", "recipient": recipient, "idempotency_key": idempotency_key}))
     }
 }
@@ -1,29 +1,136 @@
 // Decision Agent: Business rules and action prioritization
 
-use serde_json::Value;
+use serde::Deserialize;
+use serde_json::{json, Value};
 use anyhow::Result;
-use tracing::info;
+use std::env;
+use tracing::{info, warn};
 
-pub struct DecisionAgent;
+/// One routing rule: if any `patterns` substring matches the query
+/// (case-insensitive), emit an action of `action_type` targeting
+/// `target_service` with `payload_template` interpolated against the
+/// query. Rules are evaluated in ascending `priority` order.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DecisionRule {
+    pub name: String,
+    pub patterns: Vec<String>,
+    pub action_type: String,
+    pub target_service: String,
+    pub payload_template: Value,
+    #[serde(default = "default_rule_priority")]
+    pub priority: i32,
+}
+
+fn default_rule_priority() -> i32 {
+    10
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DecisionRuleSet {
+    #[serde(default)]
+    rules: Vec<DecisionRule>,
+}
+
+pub struct DecisionAgent {
+    rules: Vec<DecisionRule>,
+}
 
 impl DecisionAgent {
     pub fn new() -> Self {
-        Self
+        Self { rules: Self::load_rules() }
     }
 
-    pub async fn decide(&self, summary: &str, query: &str) -> Result<Vec<ActionDecision>> {
-        info!("Decision: Analyzing summary for required actions");
-        
-        // TODO: Apply business rules
-        // TODO: Determine action priority
-        // TODO: Create action payloads
-        
-        // Placeholder
-        Ok(vec![])
+    /// Evaluates the configured rules against the query in priority order
+    /// and returns every match as an `ActionDecision`. Operators can add
+    /// routing (e.g. a PagerDuty rule on the word "outage") or change a
+    /// recipient/priority by editing `DECISION_RULES_CONFIG`, with no
+    /// code change.
+    pub async fn decide(&self, _summary: &str, query: &str) -> Result<Vec<ActionDecision>> {
+        info!("Decision: evaluating {} rule(s) against query", self.rules.len());
+
+        let q_lower = query.to_lowercase();
+        let decisions = self
+            .rules
+            .iter()
+            .filter(|rule| rule.patterns.iter().any(|pattern| q_lower.contains(&pattern.to_lowercase())))
+            .map(|rule| ActionDecision {
+                action_type: rule.action_type.clone(),
+                target_service: rule.target_service.clone(),
+                payload: interpolate(&rule.payload_template, query),
+                priority: rule.priority,
+            })
+            .collect();
+
+        Ok(decisions)
+    }
+
+    /// Rules live in a JSON file (`DECISION_RULES_CONFIG`, a `{ "rules": [...] }`
+    /// document) so they can change without a recompile. Falls back to the
+    /// ticket/email/slack rules the query handler used to hardcode, for
+    /// deployments that predate the config file.
+    fn load_rules() -> Vec<DecisionRule> {
+        if let Ok(path) = env::var("DECISION_RULES_CONFIG") {
+            match std::fs::read_to_string(&path) {
+                Ok(contents) => match serde_json::from_str::<DecisionRuleSet>(&contents) {
+                    Ok(rule_set) => return sorted_by_priority(rule_set.rules),
+                    Err(e) => warn!("Failed to parse decision rules at {}: {}", path, e),
+                },
+                Err(e) => warn!("Failed to read decision rules at {}: {}", path, e),
+            }
+        }
+        sorted_by_priority(Self::default_rules())
+    }
+
+    fn default_rules() -> Vec<DecisionRule> {
+        vec![
+            DecisionRule {
+                name: "jira_ticket".to_string(),
+                patterns: vec!["ticket".to_string(), "incident".to_string()],
+                action_type: "JIRA_TICKET".to_string(),
+                target_service: "jira".to_string(),
+                payload_template: json!({ "description": "Create JIRA Ticket: '{query}'", "priority": "high" }),
+                priority: 1,
+            },
+            DecisionRule {
+                name: "email_alert".to_string(),
+                patterns: vec!["email".to_string(), "alert".to_string()],
+                action_type: "EMAIL_ALERT".to_string(),
+                target_service: "smtp".to_string(),
+                payload_template: json!({ "description": "Send Email: '{query}'", "recipient": "admin@example.com", "priority": "high" }),
+                priority: 2,
+            },
+            DecisionRule {
+                name: "slack_alert".to_string(),
+                patterns: vec!["slack".to_string(), "post to channel".to_string()],
+                action_type: "SLACK_ALERT".to_string(),
+                target_service: "slack".to_string(),
+                payload_template: json!({ "description": "Post to Slack Channel: '{query}'", "channel": "#general", "priority": "high" }),
+                priority: 3,
+            },
+        ]
+    }
+}
+
+fn sorted_by_priority(mut rules: Vec<DecisionRule>) -> Vec<DecisionRule> {
+    rules.sort_by_key(|rule| rule.priority);
+    rules
+}
+
+/// Replaces `{query}` placeholders anywhere in the payload template
+/// (recursively, so nested objects/arrays are supported) with the actual
+/// query text.
+fn interpolate(template: &Value, query: &str) -> Value {
+    match template {
+        Value::String(s) => Value::String(s.replace("{query}", query)),
+        Value::Array(items) => Value::Array(items.iter().map(|v| interpolate(v, query)).collect()),
+        Value::Object(map) => {
+            Value::Object(map.iter().map(|(k, v)| (k.clone(), interpolate(v, query))).collect())
+        }
+        other => other.clone(),
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ActionDecision {
     pub action_type: String,
     pub target_service: String,
@@ -1,28 +1,91 @@
 // Planner Agent: Decomposes user queries into executable steps
 
+use crate::llm::{ChatMessage, LlmClient};
 use crate::models::PlannerStep;
-use anyhow::Result;
-use tracing::info;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+const PLANNER_SYSTEM_PROMPT: &str = r#"You are a planning agent for an enterprise knowledge assistant.
+Decompose the user's query into a JSON array of steps of the form
+{"step": <int>, "action": <"retrieve"|"summarize"|"decide"|"code">, "args": {...}}.
+Use "decide" for requests to create a ticket, send an email/alert, or post to
+Slack. Use "code" for requests to calculate, solve, or compute something.
+Otherwise use "retrieve" followed by "summarize". Respond with ONLY the JSON
+array, no prose."#;
 
 pub struct PlannerAgent {
-    llm_endpoint: String,
-    api_key: Option<String>,
+    llm: Arc<dyn LlmClient>,
 }
 
 impl PlannerAgent {
-    pub fn new(llm_endpoint: String, api_key: Option<String>) -> Self {
-        Self { llm_endpoint, api_key }
+    pub fn new(llm: Arc<dyn LlmClient>) -> Self {
+        Self { llm }
     }
 
     pub async fn plan(&self, query: &str) -> Result<Vec<PlannerStep>> {
         info!("Planner: Decomposing query: {}", query);
-        
-        // TODO: Call LLM with prompt template
-        // TODO: Parse structured output
-        // TODO: Log to audit trail
-        
-        // Placeholder
-        Ok(vec![
+
+        let messages = vec![
+            ChatMessage::system(PLANNER_SYSTEM_PROMPT),
+            ChatMessage::user(query),
+        ];
+
+        let raw = match self.llm.chat(messages).await {
+            Ok(raw) => raw,
+            Err(e) => {
+                warn!("Planner: LLM call failed ({}), falling back to default plan", e);
+                return Ok(Self::default_plan(query));
+            }
+        };
+
+        match Self::parse_steps(&raw) {
+            Ok(steps) if !steps.is_empty() => Ok(steps),
+            _ => {
+                warn!("Planner: could not parse LLM plan, falling back to default plan");
+                Ok(Self::default_plan(query))
+            }
+        }
+    }
+
+    fn parse_steps(raw: &str) -> Result<Vec<PlannerStep>> {
+        let cleaned = raw.trim().trim_start_matches("```json").trim_start_matches("```").trim_end_matches("```");
+        let value: Value = serde_json::from_str(cleaned.trim()).context("planner output is not valid JSON")?;
+        let steps: Vec<PlannerStep> = serde_json::from_value(value).context("planner output does not match PlannerStep schema")?;
+        Ok(steps)
+    }
+
+    /// Used when the LLM call fails or returns an unparseable plan. Mirrors
+    /// the keyword routing the query handler used before the orchestration
+    /// pipeline existed, so behavior degrades gracefully rather than
+    /// silently dropping action requests when the planner LLM is down.
+    fn default_plan(query: &str) -> Vec<PlannerStep> {
+        let q_lower = query.to_lowercase();
+
+        if q_lower.contains("ticket")
+            || q_lower.contains("incident")
+            || q_lower.contains("email")
+            || q_lower.contains("alert")
+            || q_lower.contains("slack")
+            || q_lower.contains("post to channel")
+        {
+            return vec![PlannerStep {
+                step: 1,
+                action: "decide".to_string(),
+                args: serde_json::json!({"query": query}),
+            }];
+        }
+
+        if q_lower.contains("calculate") || q_lower.contains("solve") || q_lower.contains("math") {
+            return vec![PlannerStep {
+                step: 1,
+                action: "code".to_string(),
+                args: serde_json::json!({"query": query}),
+            }];
+        }
+
+        vec![
             PlannerStep {
                 step: 1,
                 action: "retrieve".to_string(),
@@ -33,6 +96,6 @@ impl PlannerAgent {
                 action: "summarize".to_string(),
                 args: serde_json::json!({}),
             },
-        ])
+        ]
     }
 }
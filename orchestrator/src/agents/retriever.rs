@@ -1,30 +1,255 @@
-// Retriever Agent: Performs hybrid search and re-ranking
+// Retriever Agent: Performs hybrid search and RRF re-ranking
 
-use crate::models::RetrievalResult;
+use crate::models::{Passage, RetrievalResult};
 use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::HashMap;
 use tracing::info;
+use uuid::Uuid;
+
+/// Default RRF smoothing constant. Larger values flatten the influence of
+/// rank differences between lists; 60 is the value from the original RRF
+/// paper and a common default in hybrid search implementations.
+const DEFAULT_RRF_K: f32 = 60.0;
 
 pub struct RetrieverAgent {
+    embedding_url: String,
     vector_db_url: String,
+    rrf_k: f32,
+    dense_weight: f32,
+    sparse_weight: f32,
 }
 
 impl RetrieverAgent {
-    pub fn new(vector_db_url: String) -> Self {
-        Self { vector_db_url }
+    pub fn new(embedding_url: String, vector_db_url: String) -> Self {
+        Self {
+            embedding_url,
+            vector_db_url,
+            rrf_k: DEFAULT_RRF_K,
+            dense_weight: 1.0,
+            sparse_weight: 1.0,
+        }
+    }
+
+    /// Overrides the RRF smoothing constant and per-list weights, so
+    /// operators can tune how much the keyword list counts relative to the
+    /// dense list without a code change.
+    pub fn with_rrf_params(mut self, rrf_k: f32, dense_weight: f32, sparse_weight: f32) -> Self {
+        self.rrf_k = rrf_k;
+        self.dense_weight = dense_weight;
+        self.sparse_weight = sparse_weight;
+        self
     }
 
-    pub async fn retrieve(&self, query: &str, top_k: usize) -> Result<RetrievalResult> {
+    pub async fn retrieve(&self, query: &str, top_k: usize, user_id: &str) -> Result<RetrievalResult> {
         info!("Retriever: Searching for: {}", query);
-        
-        // TODO: Call vector DB hybrid search
-        // TODO: Implement re-ranking (RRF)
-        // TODO: Log retrieved doc IDs
-        
-        // Placeholder
-        Ok(RetrievalResult {
-            passages: vec![],
-            embeddings: vec![],
-            scores: vec![],
+
+        let client = reqwest::Client::new();
+
+        let embed_resp: Value = client
+            .post(format!("{}/embed", self.embedding_url))
+            .json(&json!({ "texts": [query] }))
+            .send()
+            .await?
+            .json()
+            .await?;
+        let query_vector: Vec<f64> = embed_resp["embeddings"][0]
+            .as_array()
+            .map(|v| v.iter().filter_map(|n| n.as_f64()).collect())
+            .unwrap_or_default();
+
+        // Oversample each list so RRF has enough candidates to re-rank
+        // before we truncate to `top_k`.
+        let candidate_pool = (top_k * 4).max(20);
+        let search_resp: Value = client
+            .post(format!("{}/search/hybrid", self.vector_db_url))
+            .json(&json!({
+                "query_vector": query_vector,
+                "query_text": query,
+                "top_k": candidate_pool,
+                "hybrid": true,
+                // Every chunk is stamped with `user_id` at ingestion time
+                // (see queue.rs); scoping the search the same way keeps
+                // one tenant's documents out of another's RAG answers.
+                "user_id": user_id,
+            }))
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        // The hybrid endpoint returns the dense-vector ranking and the
+        // sparse/keyword ranking separately so we can fuse them ourselves
+        // rather than trusting a server-side blend.
+        let dense = Self::parse_ranked_list(&search_resp["dense_results"]);
+        let sparse = Self::parse_ranked_list(&search_resp["sparse_results"]);
+
+        let fused = self.fuse(&[(&dense, self.dense_weight), (&sparse, self.sparse_weight)]);
+
+        let mut passages = Vec::with_capacity(top_k);
+        let mut scores = Vec::with_capacity(top_k);
+        for (passage, score) in fused.into_iter().take(top_k) {
+            passages.push(passage);
+            scores.push(score);
+        }
+
+        Ok(RetrievalResult { passages, embeddings: vec![], scores })
+    }
+
+    /// Reciprocal Rank Fusion: `fused_score(d) = sum_L weight_L / (k + rank_L(d))`,
+    /// summed across every list `d` appears in and sorted descending.
+    fn fuse(&self, lists: &[(&Vec<Passage>, f32)]) -> Vec<(Passage, f32)> {
+        let mut scores: HashMap<Uuid, f32> = HashMap::new();
+        let mut passages_by_id: HashMap<Uuid, Passage> = HashMap::new();
+
+        for (list, weight) in lists {
+            for (rank, passage) in list.iter().enumerate() {
+                let contribution = weight / (self.rrf_k + (rank + 1) as f32);
+                *scores.entry(passage.id).or_insert(0.0) += contribution;
+                passages_by_id.entry(passage.id).or_insert_with(|| passage.clone());
+            }
+        }
+
+        let mut fused: Vec<(Passage, f32)> = scores
+            .into_iter()
+            .filter_map(|(id, score)| passages_by_id.remove(&id).map(|p| (p, score)))
+            .collect();
+        fused.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        fused
+    }
+
+    fn parse_ranked_list(value: &Value) -> Vec<Passage> {
+        value
+            .as_array()
+            .map(|matches| matches.iter().filter_map(Self::parse_passage).collect())
+            .unwrap_or_default()
+    }
+
+    /// Requires a real vector-DB `id` and `doc_id` rather than minting
+    /// random ones for missing values: `fuse()` keys on `passage.id` to
+    /// merge a match appearing in both the dense and sparse lists, and
+    /// `sources::handle_get_source` resolves citations by `doc_id` — a
+    /// random UUID would silently defeat fusion and produce citations
+    /// nothing can look up, so a match without stable IDs is dropped
+    /// instead.
+    fn parse_passage(m: &Value) -> Option<Passage> {
+        let text = m["metadata"]["text"].as_str()?.to_string();
+        let id = m["id"].as_str().and_then(|s| Uuid::parse_str(s).ok())?;
+        let doc_id = m["metadata"]["doc_id"].as_str().and_then(|s| Uuid::parse_str(s).ok())?;
+
+        Some(Passage {
+            id,
+            doc_id,
+            passage_index: m["metadata"]["passage_index"].as_i64().unwrap_or(0) as i32,
+            text,
+            char_start: 0,
+            char_end: 0,
+            page_num: m["metadata"]["page"].as_i64().map(|v| v as i32),
+            metadata: m["metadata"].clone(),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passage(id: Uuid) -> Passage {
+        Passage {
+            id,
+            doc_id: Uuid::new_v4(),
+            passage_index: 0,
+            text: "text".to_string(),
+            char_start: 0,
+            char_end: 0,
+            page_num: None,
+            metadata: Value::Null,
+        }
+    }
+
+    fn agent() -> RetrieverAgent {
+        RetrieverAgent::new("http://embed".to_string(), "http://vector".to_string())
+    }
+
+    #[test]
+    fn fuse_combines_scores_for_a_passage_in_both_lists() {
+        let agent = agent();
+        let id = Uuid::new_v4();
+        let dense = vec![passage(id)];
+        let sparse = vec![passage(id)];
+
+        let fused = agent.fuse(&[(&dense, 1.0), (&sparse, 1.0)]);
+
+        assert_eq!(fused.len(), 1);
+        let expected = 1.0 / (agent.rrf_k + 1.0) + 1.0 / (agent.rrf_k + 1.0);
+        assert!((fused[0].1 - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn fuse_keeps_a_passage_found_in_only_one_list() {
+        let agent = agent();
+        let id = Uuid::new_v4();
+        let dense = vec![passage(id)];
+        let sparse: Vec<Passage> = vec![];
+
+        let fused = agent.fuse(&[(&dense, 1.0), (&sparse, 1.0)]);
+
+        assert_eq!(fused.len(), 1);
+        assert_eq!(fused[0].0.id, id);
+        let expected = 1.0 / (agent.rrf_k + 1.0);
+        assert!((fused[0].1 - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn fuse_ranks_higher_rank_above_lower_rank() {
+        let agent = agent();
+        let first = passage(Uuid::new_v4());
+        let second = passage(Uuid::new_v4());
+        let dense = vec![first.clone(), second.clone()];
+
+        let fused = agent.fuse(&[(&dense, 1.0)]);
+
+        assert_eq!(fused[0].0.id, first.id);
+        assert_eq!(fused[1].0.id, second.id);
+    }
+
+    #[test]
+    fn fuse_respects_per_list_weight() {
+        let agent = agent();
+        let id = Uuid::new_v4();
+        let dense = vec![passage(id)];
+
+        let fused = agent.fuse(&[(&dense, 2.0)]);
+
+        let expected = 2.0 / (agent.rrf_k + 1.0);
+        assert!((fused[0].1 - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn parse_passage_drops_matches_missing_a_stable_id() {
+        let no_id = json!({
+            "metadata": { "text": "hello", "doc_id": Uuid::new_v4().to_string() }
+        });
+        assert!(RetrieverAgent::parse_passage(&no_id).is_none());
+
+        let no_doc_id = json!({
+            "id": Uuid::new_v4().to_string(),
+            "metadata": { "text": "hello" }
+        });
+        assert!(RetrieverAgent::parse_passage(&no_doc_id).is_none());
+    }
+
+    #[test]
+    fn parse_passage_keeps_the_real_vector_db_id() {
+        let id = Uuid::new_v4();
+        let doc_id = Uuid::new_v4();
+        let m = json!({
+            "id": id.to_string(),
+            "metadata": { "text": "hello", "doc_id": doc_id.to_string() }
+        });
+
+        let passage = RetrieverAgent::parse_passage(&m).unwrap();
+        assert_eq!(passage.id, id);
+        assert_eq!(passage.doc_id, doc_id);
+    }
+}
@@ -1,25 +1,28 @@
 use warp::{Rejection, Reply};
+use crate::agents::action::ActionAgent;
 use crate::db::DbPool;
 use crate::redis_client::RedisClient;
+use crate::webauthn::{self, WebauthnCtx};
+use crate::models::ApprovalResponse;
 use uuid::Uuid;
-use tracing::{info, error};
+use tracing::{info, error, warn};
 use serde::{Serialize, Deserialize};
 use sqlx::FromRow;
 use serde_json::{json, Value};
-// Email Imports
-use lettre::{Message, AsyncTransport, Tokio1Executor, AsyncSmtpTransport};
-use lettre::message::Mailbox; 
-use lettre::transport::smtp::authentication::Credentials;
-// NEW IMPORTS FOR TLS CONFIGURATION
-use lettre::transport::smtp::client::{Tls, TlsParameters};
-use std::env;
-
-#[derive(Serialize, FromRow)]
-struct PendingActionDTO {
-    id: Uuid,
-    action_type: String,
-    payload: Value,
-    status: String,
+use std::sync::Arc;
+use webauthn_rs::prelude::PublicKeyCredential;
+use std::time::Duration;
+
+/// Attempts before an approved action is given up on and moved to the
+/// `action_errors` dead-letter table.
+const MAX_EXECUTION_ATTEMPTS: u32 = 3;
+
+#[derive(Serialize, FromRow, Clone)]
+pub struct PendingActionDTO {
+    pub id: Uuid,
+    pub action_type: String,
+    pub payload: Value,
+    pub status: String,
 }
 
 pub async fn handle_get_pending(
@@ -44,141 +47,280 @@ pub async fn handle_get_pending(
 pub struct ApproveRequest {
     pub action_id: Uuid,
     pub approved: bool,
-    pub user_signature: String,
+    pub user_id: String,
+    /// Required when `approved` is true: the id of the challenge issued by
+    /// `POST /api/v1/approve/challenge` and the browser's signed assertion
+    /// over it. A rejection doesn't execute anything, so it's allowed
+    /// through without a signature.
+    pub challenge_id: Option<Uuid>,
+    pub assertion: Option<PublicKeyCredential>,
+}
+
+#[derive(Deserialize)]
+pub struct ChallengeRequest {
+    pub action_id: Uuid,
+    pub user_id: String,
+}
+
+pub async fn handle_approval_challenge(
+    request: ChallengeRequest,
+    db_pool: DbPool,
+    webauthn_ctx: Arc<WebauthnCtx>,
+) -> Result<impl Reply, Rejection> {
+    let action = match sqlx::query_as::<_, PendingActionDTO>(
+        "SELECT id, action_type, payload, status FROM pending_actions WHERE id = $1"
+    ).bind(request.action_id).fetch_optional(&db_pool).await {
+        Ok(Some(action)) => action,
+        Ok(None) => return Ok(warp::reply::json(&json!({"status": "error", "message": "action not found"}))),
+        Err(e) => return Ok(warp::reply::json(&json!({"status": "error", "message": e.to_string()}))),
+    };
+
+    match webauthn_ctx.start_approval_challenge(&db_pool, &request.user_id, &action).await {
+        Ok((challenge_id, challenge)) => Ok(warp::reply::json(&json!({
+            "status": "success",
+            "challenge_id": challenge_id,
+            "publicKey": challenge.public_key,
+        }))),
+        Err(e) => {
+            warn!("Failed to start approval challenge for action {}: {}", request.action_id, e);
+            Ok(warp::reply::json(&json!({"status": "error", "message": e.to_string()})))
+        }
+    }
 }
 
 pub async fn handle_approve(
     request: ApproveRequest,
     db_pool: DbPool,
-    mut _redis_client: RedisClient,
+    mut redis_client: RedisClient,
+    webauthn_ctx: Arc<WebauthnCtx>,
 ) -> Result<impl Reply, Rejection> {
     info!("Processing approval for action {}", request.action_id);
     let status = if request.approved { "approved" } else { "rejected" };
 
+    let action_row = sqlx::query_as::<_, PendingActionDTO>(
+        "SELECT id, action_type, payload, status FROM pending_actions WHERE id = $1"
+    ).bind(request.action_id).fetch_optional(&db_pool).await;
+    let action = match action_row {
+        Ok(Some(action)) => action,
+        Ok(None) => return Ok(warp::reply::json(&json!({"status": "error", "message": "action not found"}))),
+        Err(e) => return Ok(warp::reply::json(&json!({"status": "error", "message": e.to_string()}))),
+    };
+
+    // Approving triggers a real external side effect, so it must be backed
+    // by a verified WebAuthn assertion bound to this exact action + payload;
+    // a rejection is a no-op and needs no proof.
+    let approved_by = if request.approved {
+        let (challenge_id, assertion) = match (request.challenge_id, &request.assertion) {
+            (Some(challenge_id), Some(assertion)) => (challenge_id, assertion),
+            _ => return Ok(warp::reply::json(&json!({
+                "status": "error", "message": "approval requires challenge_id and assertion"
+            }))),
+        };
+
+        let verified = match webauthn_ctx.verify_approval(&db_pool, challenge_id, assertion).await {
+            Ok(verified) => verified,
+            Err(e) => {
+                warn!("WebAuthn verification failed for action {}: {}", request.action_id, e);
+                return Ok(warp::reply::json(&json!({"status": "error", "message": e.to_string()})));
+            }
+        };
+
+        if verified.user_id != request.user_id {
+            return Ok(warp::reply::json(&json!({"status": "error", "message": "challenge was issued to a different user"})));
+        }
+
+        let current_hash = webauthn::hash_payload(action.id, &action.payload);
+        if verified.payload_hash != current_hash {
+            warn!("Payload hash mismatch for action {}: challenge bound to a different payload", request.action_id);
+            return Ok(warp::reply::json(&json!({
+                "status": "error", "message": "action payload changed since the approval challenge was issued"
+            })));
+        }
+
+        let _ = sqlx::query(
+            "INSERT INTO audit_log (id, request_id, task_id, event_type, actor, timestamp, details) \
+             VALUES ($1, NULL, NULL, 'action_approved', $2, NOW(), $3)"
+        )
+        .bind(Uuid::new_v4())
+        .bind(&verified.user_id)
+        .bind(json!({
+            "action_id": request.action_id,
+            "payload_hash": verified.payload_hash,
+            "credential_id": verified.credential_id,
+            "signature_counter": verified.counter,
+        }))
+        .execute(&db_pool).await;
+
+        verified.user_id
+    } else {
+        request.user_id.clone()
+    };
+
     // 1. Update Database Status
     let update_result = sqlx::query(
         "UPDATE pending_actions SET status = $1, approved_at = NOW(), approved_by = $2 WHERE id = $3"
-    ).bind(status).bind(&request.user_signature).bind(request.action_id).execute(&db_pool).await;
+    ).bind(status).bind(&approved_by).bind(request.action_id).execute(&db_pool).await;
 
     if let Err(e) = update_result {
         return Ok(warp::reply::json(&serde_json::json!({"status": "error", "message": e.to_string()})));
     }
 
-    // 2. EXECUTE REAL ACTION
-    if request.approved {
-        let action_row = sqlx::query_as::<_, PendingActionDTO>(
-            "SELECT id, action_type, payload, status FROM pending_actions WHERE id = $1"
-        ).bind(request.action_id).fetch_optional(&db_pool).await;
-
-        if let Ok(Some(action)) = action_row {
-            if action.action_type == "EMAIL_ALERT" {
-                info!("Executing Email Action...");
-                if let Err(e) = send_real_email(&action.payload).await {
-                    error!("Failed to send email: {}", e);
-                }
-            } else if action.action_type == "JIRA_TICKET" {
-                info!("Executing Jira Action...");
-                if let Err(e) = create_real_jira_ticket(&action.payload).await {
-                    error!("Failed to create Jira ticket: {}", e);
-                }
-            } else if action.action_type == "SLACK_ALERT" {
-                info!("Executing Slack Action...");
-                if let Err(e) = post_slack_message(&action.payload, &request.user_signature).await {
-                    error!("Failed to post Slack message: {}", e);
-                }
-            }
+    // 2. EXECUTE REAL ACTION (durably: retried, and dead-lettered on exhaustion)
+    let execution_result = if request.approved {
+        execute_action(&db_pool, &action, &approved_by).await.ok()
+    } else {
+        None
+    };
+
+    let event = serde_json::json!({
+        "action_id": request.action_id,
+        "status": status,
+        "action_type": action.action_type,
+    }).to_string();
+    if let Err(e) = redis_client.publish(crate::api::ws::PENDING_ACTIONS_CHANNEL, &event).await {
+        warn!("Failed to publish approval event for {}: {}", request.action_id, e);
+    }
+
+    Ok(warp::reply::json(&ApprovalResponse {
+        action_id: request.action_id,
+        executed: execution_result.is_some(),
+        result: execution_result,
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct RegisterStartRequest {
+    pub user_id: String,
+}
+
+pub async fn handle_webauthn_register_start(
+    request: RegisterStartRequest,
+    db_pool: DbPool,
+    webauthn_ctx: Arc<WebauthnCtx>,
+) -> Result<impl Reply, Rejection> {
+    match webauthn_ctx.start_registration(&db_pool, &request.user_id).await {
+        Ok((challenge_id, challenge)) => Ok(warp::reply::json(&json!({
+            "status": "success",
+            "challenge_id": challenge_id,
+            "publicKey": challenge.public_key,
+        }))),
+        Err(e) => {
+            error!("Failed to start WebAuthn registration for {}: {}", request.user_id, e);
+            Ok(warp::reply::json(&json!({"status": "error", "message": e.to_string()})))
         }
     }
-    Ok(warp::reply::json(&serde_json::json!({"status": "success"})))
 }
 
-// --- HELPER: SEND EMAIL ---
-async fn send_real_email(payload: &Value) -> Result<(), String> {
-    let smtp_host = env::var("SMTP_HOST").unwrap_or("smtp.gmail.com".to_string());
-    let smtp_user = env::var("SMTP_USER").unwrap_or("".to_string());
-    let smtp_pass = env::var("SMTP_PASS").unwrap_or("".to_string());
-
-    if smtp_user.is_empty() || smtp_pass.is_empty() { return Err("SMTP creds missing".to_string()); }
-
-    let description = payload["description"].as_str().unwrap_or("No description");
-    let mut recipient = payload["recipient"].as_str().unwrap_or("admin@example.com");
-    if recipient == "admin@example.com" { recipient = &smtp_user; }
-
-    info!("Sending email to: {}", recipient);
-
-    let email = Message::builder()
-        .from(format!("Agentic AI <{}>", smtp_user).parse::<Mailbox>().unwrap())
-        .to(recipient.parse::<Mailbox>().map_err(|e| e.to_string())?)
-        .subject("🚨 Agentic AI Alert")
-        .body(format!("Action executed:\n\n{}", description))
-        .map_err(|e| e.to_string())?;
-
-    let creds = Credentials::new(smtp_user, smtp_pass);
-    
-    // --- THE FIX: Force Port 465 + Wrapper TLS ---
-    let tls_parameters = TlsParameters::new(smtp_host.clone())
-        .map_err(|e| e.to_string())?;
-
-    let mailer: AsyncSmtpTransport<Tokio1Executor> = AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp_host)
-        .map_err(|e| e.to_string())?
-        .port(465) 
-        .tls(Tls::Wrapper(tls_parameters)) // Forces SSL/TLS immediately
-        .credentials(creds)
-        .build();
-
-    mailer.send(email).await.map_err(|e| e.to_string())?;
-    Ok(())
+#[derive(Deserialize)]
+pub struct RegisterFinishRequest {
+    pub challenge_id: Uuid,
+    pub credential: webauthn_rs::prelude::RegisterPublicKeyCredential,
+}
+
+pub async fn handle_webauthn_register_finish(
+    request: RegisterFinishRequest,
+    db_pool: DbPool,
+    webauthn_ctx: Arc<WebauthnCtx>,
+) -> Result<impl Reply, Rejection> {
+    match webauthn_ctx.finish_registration(&db_pool, request.challenge_id, &request.credential).await {
+        Ok(()) => Ok(warp::reply::json(&json!({"status": "success"}))),
+        Err(e) => {
+            error!("Failed to finish WebAuthn registration for challenge {}: {}", request.challenge_id, e);
+            Ok(warp::reply::json(&json!({"status": "error", "message": e.to_string()})))
+        }
+    }
 }
 
-// --- HELPER: CREATE JIRA TICKET ---
-async fn create_real_jira_ticket(payload: &Value) -> Result<(), String> {
-    let domain = env::var("JIRA_DOMAIN").unwrap_or_default();
-    let user = env::var("JIRA_USER").unwrap_or_default();
-    let token = env::var("JIRA_TOKEN").unwrap_or_default();
-    let project_key = env::var("JIRA_PROJECT_KEY").unwrap_or("KAN".to_string());
-
-    if domain.is_empty() { return Err("Jira credentials missing".to_string()); }
-
-    let summary = payload["description"].as_str().unwrap_or("AI Generated Ticket");
-    let url = format!("{}/rest/api/3/issue", domain);
-    let client = reqwest::Client::new();
-
-    let body = json!({
-        "fields": {
-            "project": { "key": project_key },
-            "summary": summary,
-            "description": {
-                "type": "doc", "version": 1, 
-                "content": [{ "type": "paragraph", "content": [{ "type": "text", "text": format!("Auto-created by AI.\n\n{}", summary) }] }]
-            },
-            "issuetype": { "name": "Task" }
+/// Dispatches an approved action to its target service via `ActionAgent`'s
+/// signed, idempotent connectors, retrying transient failures with backoff
+/// and recording the outcome (`executed` + the connector's response, or
+/// `failed` + a dead-lettered `action_errors` row) on `pending_actions`.
+/// Shared between the approval handler and the crash-recovery worker so a
+/// restart can re-drain rows stuck in `approved`-but-not-`executed`.
+pub async fn execute_action(db_pool: &DbPool, action: &PendingActionDTO, approved_by: &str) -> Result<Value, String> {
+    let agent = ActionAgent::new();
+    let action_id = action.id;
+    let action_type = action.action_type.clone();
+    let payload = action.payload.clone();
+
+    let outcome = execute_with_retries(db_pool, action_id, move || {
+        let agent = &agent;
+        let action_type = action_type.clone();
+        let payload = payload.clone();
+        async move {
+            agent.execute(action_id, &action_type, &payload).await.map_err(|e| e.to_string())
         }
-    });
+    })
+    .await;
 
-    let resp = client.post(url).basic_auth(user, Some(token)).json(&body).send().await.map_err(|e| e.to_string())?;
-    
-    if resp.status().is_success() {
-        info!("✅ Jira Ticket Created!");
-        Ok(())
-    } else {
-        let error_text = resp.text().await.unwrap_or_default();
-        Err(format!("Jira API Error: {}", error_text))
+    match &outcome {
+        Ok(result) => {
+            let _ = sqlx::query(
+                "INSERT INTO audit_log (id, request_id, task_id, event_type, actor, timestamp, details) \
+                 VALUES ($1, NULL, NULL, 'action_executed', $2, NOW(), $3)"
+            )
+            .bind(Uuid::new_v4())
+            .bind(approved_by)
+            .bind(json!({"action_id": action.id, "action_type": action.action_type, "result": result}))
+            .execute(db_pool).await;
+        }
+        Err(e) => {
+            error!("Action {} exhausted retries and was dead-lettered: {}", action.id, e);
+            let _ = sqlx::query(
+                "INSERT INTO audit_log (id, request_id, task_id, event_type, actor, timestamp, details) \
+                 VALUES ($1, NULL, NULL, 'action_execution_failed', $2, NOW(), $3)"
+            )
+            .bind(Uuid::new_v4())
+            .bind(approved_by)
+            .bind(json!({"action_id": action.id, "action_type": action.action_type, "error": e}))
+            .execute(db_pool).await;
+        }
     }
+
+    outcome
 }
 
-// --- HELPER: POST SLACK MESSAGE ---
-async fn post_slack_message(payload: &Value, user_signature: &str) -> Result<(), String> {
-    let webhook_url = env::var("SLACK_WEBHOOK_URL").unwrap_or_default();
-    if webhook_url.is_empty() { return Err("SLACK_WEBHOOK_URL is not set".to_string()); }
+/// Runs `op` up to `MAX_EXECUTION_ATTEMPTS` times with exponential backoff,
+/// persisting `attempts`/`last_error` after each failure. On success marks
+/// `execution_status = 'executed'` and stores the connector's response on
+/// `execution_result`; on exhaustion marks `'failed'` and writes the final
+/// error to the `action_errors` dead-letter table instead of dropping it.
+async fn execute_with_retries<F, Fut>(db_pool: &DbPool, action_id: Uuid, mut op: F) -> Result<Value, String>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<Value, String>>,
+{
+    let _ = sqlx::query("UPDATE pending_actions SET execution_status = 'executing', execution_status_at = NOW() WHERE id = $1")
+        .bind(action_id).execute(db_pool).await;
+
+    let mut last_error = String::new();
+    for attempt in 1..=MAX_EXECUTION_ATTEMPTS {
+        match op().await {
+            Ok(result) => {
+                let _ = sqlx::query(
+                    "UPDATE pending_actions SET execution_status = 'executed', execution_status_at = NOW(), attempts = $1, execution_result = $2 WHERE id = $3"
+                ).bind(attempt as i32).bind(&result).bind(action_id).execute(db_pool).await;
+                return Ok(result);
+            }
+            Err(e) => {
+                warn!("Action {} attempt {}/{} failed: {}", action_id, attempt, MAX_EXECUTION_ATTEMPTS, e);
+                last_error = e;
+                let _ = sqlx::query("UPDATE pending_actions SET attempts = $1, last_error = $2 WHERE id = $3")
+                    .bind(attempt as i32).bind(&last_error).bind(action_id).execute(db_pool).await;
 
-    let description = payload["description"].as_str().unwrap_or("Alert from Agentic AI");
-    let message = json!({
-        "text": format!("🔔 *Action Approved by {}*\n\n*Action:* Slack Alert\n*Details:* {}", user_signature, description),
-        "mrkdwn": true
-    });
+                if attempt < MAX_EXECUTION_ATTEMPTS {
+                    let backoff = Duration::from_millis(200 * 2u64.pow(attempt - 1));
+                    tokio::time::sleep(backoff).await;
+                }
+            }
+        }
+    }
 
-    let client = reqwest::Client::new();
-    let resp = client.post(webhook_url).json(&message).send().await.map_err(|e| e.to_string())?;
+    let _ = sqlx::query("UPDATE pending_actions SET execution_status = 'failed', execution_status_at = NOW() WHERE id = $1")
+        .bind(action_id).execute(db_pool).await;
+    let _ = sqlx::query(
+        "INSERT INTO action_errors (id, action_id, error, created_at) VALUES ($1, $2, $3, NOW())"
+    ).bind(Uuid::new_v4()).bind(action_id).bind(&last_error).execute(db_pool).await;
 
-    if resp.status().is_success() { Ok(()) } else { Err(format!("Slack API Error: {}", resp.status())) }
+    Err(last_error)
 }
\ No newline at end of file
@@ -1,18 +1,21 @@
 use warp::{Rejection, Reply, multipart::{FormData, Part}};
 use crate::db::DbPool;
-use crate::models::{IngestResponse};
+use crate::models::IngestResponse;
+use crate::queue;
+use crate::store::Store;
 use uuid::Uuid;
 use futures::{StreamExt, TryStreamExt};
-use tracing::{info, error};
-use serde_json::{json, Value};
+use tracing::{info, error, warn};
+use serde_json::json;
 use bytes::Buf;
-use std::env;
+use std::sync::Arc;
 
 pub async fn handle_ingest(
     mut form: FormData,
     db_pool: DbPool,
+    doc_store: Arc<dyn Store>,
 ) -> Result<impl Reply, Rejection> {
-    info!("Starting document ingestion...");
+    info!("Accepting document for ingestion...");
 
     let mut filename = String::from("unknown_file");
     let mut content_type = String::from("application/octet-stream");
@@ -21,7 +24,7 @@ pub async fn handle_ingest(
 
     while let Ok(Some(part)) = form.try_next().await {
         let name = part.name().to_string();
-        
+
         if name == "file" {
             filename = part.filename().unwrap_or("unknown").to_string();
             content_type = part.content_type().unwrap_or("application/octet-stream").to_string();
@@ -44,68 +47,43 @@ pub async fn handle_ingest(
         return Ok(warp::reply::json(&json!({"error": "No file uploaded"})));
     }
 
-    info!("Ingesting file '{}' for User: {}", filename, user_id);
-    let client = reqwest::Client::new();
-
-    // 1. EXTRACT
-    let ingest_url = env::var("INGESTION_SERVICE_URL").unwrap_or("http://ingestion-service:8001".to_string());
-    let part = reqwest::multipart::Part::bytes(file_bytes)
-        .file_name(filename.clone())
-        .mime_str(&content_type)
-        .map_err(|_| warp::reject::not_found())?;
-    
-    let multipart_form = reqwest::multipart::Form::new().part("file", part);
-    let ingest_res = client.post(format!("{}/extract", ingest_url))
-        .multipart(multipart_form)
-        .send().await.map_err(|_| warp::reject::not_found())?;
-
-    let extraction_data: Value = ingest_res.json().await.map_err(|_| warp::reject::not_found())?;
-    let passages = extraction_data["passages"].as_array().ok_or_else(warp::reject::not_found)?;
-    let total_chars = extraction_data["total_chars"].as_u64().unwrap_or(0);
+    let document_id = Uuid::new_v4();
+    info!("Queuing file '{}' for User: {} (document {})", filename, user_id, document_id);
 
-    // 2. SAVE DB
-    let doc_id = Uuid::new_v4();
-    let _ = sqlx::query("INSERT INTO documents (id, filename, content_type, s3_key, upload_time, user_id, metadata) VALUES ($1, $2, $3, $4, NOW(), $5, $6)")
-        .bind(doc_id).bind(&filename).bind(&content_type).bind("local").bind(&user_id).bind(json!({ "total_chars": total_chars as i64 }))
-        .execute(&db_pool).await;
-
-    // 3. EMBED & INDEX
-    let mut texts_to_embed = Vec::new();
-    let mut metadatas = Vec::new();
+    // Persist the original file through the pluggable Store (filesystem or
+    // S3) up front, under the same key `queue::run_pipeline` records as the
+    // document's `s3_key`, so `GET /api/v1/sources/{id}` can serve it back
+    // regardless of which backend is configured.
+    let storage_key = queue::storage_key_for(document_id, &filename);
+    if let Err(e) = doc_store.put(&storage_key, file_bytes.clone(), &content_type).await {
+        warn!("Failed to persist original document {} to storage: {}", document_id, e);
+    }
 
-    for (i, p) in passages.iter().enumerate() {
-        let text = p["text"].as_str().unwrap_or("").to_string();
-        let page = p["page"].as_i64();
-        
-        if !text.trim().is_empty() {
-            texts_to_embed.push(text.clone());
-            metadatas.push(json!({
-                "text": text,
-                "doc_id": doc_id.to_string(),
-                "page": page,
-                "filename": filename,
-                "user_id": user_id  // <--- CRITICAL: ATTACH USER ID TO VECTOR
-            }));
+    let job_id = match queue::enqueue(&db_pool, document_id, &filename, &content_type, &user_id, file_bytes).await {
+        Ok(job_id) => job_id,
+        Err(e) => {
+            error!("Failed to enqueue ingestion job: {}", e);
+            return Ok(warp::reply::json(&json!({"error": "Failed to queue document for ingestion"})));
         }
-    }
+    };
 
-    if !texts_to_embed.is_empty() {
-        let embed_url = env::var("EMBEDDING_SERVICE_URL").unwrap_or("http://embedding-service:8002".to_string());
-        let vector_url = env::var("VECTOR_DB_SERVICE_URL").unwrap_or("http://vector-db-service:8003".to_string());
+    let response = IngestResponse { job_id, document_id, filename, status: "queued".to_string() };
+    Ok(warp::reply::json(&response))
+}
 
-        for (chunk_texts, chunk_metas) in texts_to_embed.chunks(50).zip(metadatas.chunks(50)) {
-            let embed_req = json!({ "texts": chunk_texts });
-            if let Ok(resp) = client.post(format!("{}/embed", embed_url)).json(&embed_req).send().await {
-                if let Ok(embed_data) = resp.json::<Value>().await {
-                    if let Some(embeddings) = embed_data["embeddings"].as_array() {
-                        let add_req = json!({ "vectors": embeddings, "metadata": chunk_metas });
-                        let _ = client.post(format!("{}/index/add", vector_url)).json(&add_req).send().await;
-                    }
-                }
-            }
+/// Lets a client poll `extract -> embed -> index` progress for a job queued
+/// by `handle_ingest`, since the upload response no longer waits for the
+/// pipeline to finish.
+pub async fn handle_get_status(
+    job_id: Uuid,
+    db_pool: DbPool,
+) -> Result<impl Reply, Rejection> {
+    match queue::status(&db_pool, job_id).await {
+        Ok(Some(job)) => Ok(warp::reply::json(&job)),
+        Ok(None) => Ok(warp::reply::json(&json!({"error": "job not found"}))),
+        Err(e) => {
+            error!("Failed to fetch ingestion job {}: {}", job_id, e);
+            Ok(warp::reply::json(&json!({"error": "failed to fetch job status"})))
         }
     }
-
-    let response = IngestResponse { document_id: doc_id, filename, passages_count: passages.len() };
-    Ok(warp::reply::json(&response))
-}
\ No newline at end of file
+}
@@ -1,15 +1,25 @@
 use warp::{Filter, Rejection, Reply};
 use crate::db::DbPool;
+use crate::llm::LlmRegistry;
 use crate::redis_client::RedisClient;
+use crate::store::Store;
+use crate::stream_hub::StreamHub;
+use crate::webauthn::WebauthnCtx;
+use std::sync::Arc;
 
 mod ingest;
 mod query;
-mod actions;
+pub(crate) mod actions;
 mod sources;
+pub(crate) mod ws;
 
 pub fn routes(
     db_pool: DbPool,
     redis_client: RedisClient,
+    llm_registry: LlmRegistry,
+    doc_store: Arc<dyn Store>,
+    stream_hub: Arc<StreamHub>,
+    webauthn_ctx: Arc<WebauthnCtx>,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     let api = warp::path("api").and(warp::path("v1"));
 
@@ -18,16 +28,39 @@ pub fn routes(
         .and(warp::post())
         .and(warp::multipart::form().max_length(100 * 1024 * 1024)) // 100MB max
         .and(with_db(db_pool.clone()))
+        .and(with_store(doc_store.clone()))
         .and_then(ingest::handle_ingest);
 
     let query_route = api
         .and(warp::path("query"))
+        .and(warp::path::end())
         .and(warp::post())
         .and(warp::body::json())
         .and(with_db(db_pool.clone()))
         .and(with_redis(redis_client.clone()))
+        .and(with_llm(llm_registry.clone()))
         .and_then(query::handle_query);
 
+    let query_stream_route = api
+        .and(warp::path("query"))
+        .and(warp::path("stream"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(warp::query())
+        .and(with_db(db_pool.clone()))
+        .and(with_redis(redis_client.clone()))
+        .and(with_llm(llm_registry.clone()))
+        .and(with_stream_hub(stream_hub.clone()))
+        .and_then(query::handle_query_stream);
+
+    let status_route = api
+        .and(warp::path("status"))
+        .and(warp::path::param())
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(with_db(db_pool.clone()))
+        .and_then(ingest::handle_get_status);
+
     let pending_route = api
         .and(warp::path("pending"))
         .and(warp::get())
@@ -35,26 +68,78 @@ pub fn routes(
         .and(with_db(db_pool.clone()))
         .and_then(actions::handle_get_pending);
 
+    let approve_challenge_route = api
+        .and(warp::path("approve"))
+        .and(warp::path("challenge"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_db(db_pool.clone()))
+        .and(with_webauthn(webauthn_ctx.clone()))
+        .and_then(actions::handle_approval_challenge);
+
     let approve_route = api
         .and(warp::path("approve"))
+        .and(warp::path::end())
         .and(warp::post())
         .and(warp::body::json())
         .and(with_db(db_pool.clone()))
         .and(with_redis(redis_client.clone()))
+        .and(with_webauthn(webauthn_ctx.clone()))
         .and_then(actions::handle_approve);
 
+    let webauthn_register_start_route = api
+        .and(warp::path("webauthn"))
+        .and(warp::path("register"))
+        .and(warp::path("start"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_db(db_pool.clone()))
+        .and(with_webauthn(webauthn_ctx.clone()))
+        .and_then(actions::handle_webauthn_register_start);
+
+    let webauthn_register_finish_route = api
+        .and(warp::path("webauthn"))
+        .and(warp::path("register"))
+        .and(warp::path("finish"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_db(db_pool.clone()))
+        .and(with_webauthn(webauthn_ctx.clone()))
+        .and_then(actions::handle_webauthn_register_finish);
+
     let sources_route = api
         .and(warp::path("sources"))
         .and(warp::path::param())
         .and(warp::get())
+        .and(warp::header::optional::<String>("range"))
         .and(with_db(db_pool.clone()))
+        .and(with_store(doc_store.clone()))
         .and_then(sources::handle_get_source);
 
+    let pending_ws_route = api
+        .and(warp::path("ws"))
+        .and(warp::path("pending"))
+        .and(warp::path::end())
+        .and(warp::ws())
+        .and(with_redis(redis_client.clone()))
+        .map(|ws: warp::ws::Ws, redis_client: RedisClient| {
+            ws.on_upgrade(move |socket| ws::handle_socket(socket, redis_client))
+        });
+
     ingest_route
         .or(query_route)
+        .or(query_stream_route)
+        .or(status_route)
         .or(pending_route)
+        .or(approve_challenge_route)
         .or(approve_route)
+        .or(webauthn_register_start_route)
+        .or(webauthn_register_finish_route)
         .or(sources_route)
+        .or(pending_ws_route)
 }
 
 fn with_db(
@@ -68,3 +153,27 @@ fn with_redis(
 ) -> impl Filter<Extract = (RedisClient,), Error = std::convert::Infallible> + Clone {
     warp::any().map(move || redis_client.clone())
 }
+
+fn with_llm(
+    llm_registry: LlmRegistry,
+) -> impl Filter<Extract = (LlmRegistry,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || llm_registry.clone())
+}
+
+fn with_store(
+    doc_store: Arc<dyn Store>,
+) -> impl Filter<Extract = (Arc<dyn Store>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || doc_store.clone())
+}
+
+fn with_stream_hub(
+    stream_hub: Arc<StreamHub>,
+) -> impl Filter<Extract = (Arc<StreamHub>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || stream_hub.clone())
+}
+
+fn with_webauthn(
+    webauthn_ctx: Arc<WebauthnCtx>,
+) -> impl Filter<Extract = (Arc<WebauthnCtx>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || webauthn_ctx.clone())
+}
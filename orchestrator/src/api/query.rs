@@ -1,153 +1,272 @@
 use warp::{Rejection, Reply};
+use crate::agents::decision::{ActionDecision, DecisionAgent};
+use crate::agents::planner::PlannerAgent;
+use crate::agents::retriever::RetrieverAgent;
+use crate::agents::summarizer::{summarizer_channel, SummarizerAgent};
+use crate::api::ws;
 use crate::db::DbPool;
+use crate::llm::LlmRegistry;
+use crate::orchestration::Orchestrator;
 use crate::redis_client::RedisClient;
-use crate::models::{QueryRequest, QueryResponse, Citation};
+use crate::stream_hub::{StreamEvent, StreamHub};
+use crate::models::{QueryRequest, QueryResponse, RetrievalResult};
+use serde::Deserialize;
+use std::sync::Arc;
 use uuid::Uuid;
 use tracing::{info, error, warn};
-use serde_json::{json, Value};
+use futures::StreamExt;
 use std::env;
 
 pub async fn handle_query(
     request: QueryRequest,
     db_pool: DbPool,
-    mut _redis_client: RedisClient,
+    mut redis_client: RedisClient,
+    llm_registry: LlmRegistry,
 ) -> Result<impl Reply, Rejection> {
     let request_id = Uuid::new_v4();
     info!("Processing query for User {}: {}", request.user_id, request.query);
-    
+
     // 1. Log Request
     let _ = sqlx::query("INSERT INTO requests (id, user_id, query, status, created_at) VALUES ($1, $2, $3, $4, NOW())")
         .bind(request_id).bind(&request.user_id).bind(&request.query).bind("processing").execute(&db_pool).await;
 
-    let q_lower = request.query.to_lowercase();
-    let mut pending_action_ids = vec![];
-    let client = reqwest::Client::new();
-    
-    // --- PATH A: DECISION LOGIC (Tickets) ---
-    if q_lower.contains("ticket") || q_lower.contains("incident") {
-        let action_id = Uuid::new_v4();
-        let payload = json!({ "description": format!("Create JIRA Ticket: '{}'", request.query), "priority": "high" });
-        let _ = sqlx::query("INSERT INTO pending_actions (id, request_id, action_type, target_service, payload, status, created_at) VALUES ($1, $2, $3, $4, $5, $6, NOW())")
-            .bind(action_id).bind(request_id).bind("JIRA_TICKET").bind("jira").bind(payload).bind("pending").execute(&db_pool).await;
-        pending_action_ids.push(action_id);
-        return Ok(warp::reply::json(&QueryResponse { request_id, summary: format!("✅ Prepared JIRA ticket (Action ID: {})", action_id), citations: vec![], pending_actions: pending_action_ids }));
-    } 
-    
-    // --- PATH B: DECISION LOGIC (Emails) ---
-    else if q_lower.contains("email") || q_lower.contains("alert") {
-        let action_id = Uuid::new_v4();
-        let payload = json!({ "description": format!("Send Email: '{}'", request.query), "recipient": "admin@example.com", "priority": "high" });
-        let _ = sqlx::query("INSERT INTO pending_actions (id, request_id, action_type, target_service, payload, status, created_at) VALUES ($1, $2, $3, $4, $5, $6, NOW())")
-            .bind(action_id).bind(request_id).bind("EMAIL_ALERT").bind("smtp").bind(payload).bind("pending").execute(&db_pool).await;
-        pending_action_ids.push(action_id);
-        return Ok(warp::reply::json(&QueryResponse { request_id, summary: format!("✅ Drafted Email Alert (Action ID: {})", action_id), citations: vec![], pending_actions: pending_action_ids }));
-    }
-    
-    // --- PATH B: DECISION LOGIC (Slack) --- <--- NEW SLACK BLOCK
-    else if q_lower.contains("slack") || q_lower.contains("post to channel") {
+    // 2. Run the query through Planner -> Retriever -> Summarizer/Decision.
+    // The planner decides which of those stages actually run for this query.
+    let orchestrator = build_orchestrator(&llm_registry);
+    let outcome = match orchestrator.run(&request.query, &request.user_id).await {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            error!("Orchestration failed for request {}: {}", request_id, e);
+            return Ok(warp::reply::json(&QueryResponse {
+                request_id,
+                summary: "Error".to_string(),
+                citations: vec![],
+                pending_actions: vec![],
+            }));
+        }
+    };
+
+    // 3. Persist any actions the Decision stage raised, same as before but
+    // driven by `ActionDecision`s instead of hand-coded keyword branches.
+    let mut pending_action_ids = Vec::with_capacity(outcome.decisions.len());
+    for decision in &outcome.decisions {
         let action_id = Uuid::new_v4();
-        let payload = json!({ 
-            "description": format!("Post to Slack Channel: '{}'", request.query), 
-            "channel": "#general",
-            "priority": "high" 
-        });
-        
         let _ = sqlx::query("INSERT INTO pending_actions (id, request_id, action_type, target_service, payload, status, created_at) VALUES ($1, $2, $3, $4, $5, $6, NOW())")
-            .bind(action_id).bind(request_id).bind("SLACK_ALERT").bind("slack").bind(payload).bind("pending")
+            .bind(action_id).bind(request_id).bind(&decision.action_type).bind(&decision.target_service).bind(&decision.payload).bind("pending")
             .execute(&db_pool).await;
-            
         pending_action_ids.push(action_id);
-        
-        return Ok(warp::reply::json(&QueryResponse {
-            request_id,
-            summary: format!("✅ I have drafted a Slack message. Check the 'Pending Actions' tab to approve and post it."),
-            citations: vec![],
-            pending_actions: pending_action_ids,
-        }));
-    }
 
-    // --- PATH C: SANDBOXED CODE EXECUTION (Math/Logic) ---
-    else if q_lower.contains("calculate") || q_lower.contains("solve") || q_lower.contains("math") {
-        let groq_api_key = env::var("GROQ_API_KEY").unwrap_or_default();
-        if !groq_api_key.is_empty() {
-            let code_prompt = json!({
-                "model": "llama-3.3-70b-versatile",
-                "messages": [
-                    { "role": "system", "content": "You are a Python Coding Assistant. Output ONLY valid Python code to solve the user's problem. Use 'print()' to output the final answer." },
-                    { "role": "user", "content": request.query }
-                ]
-            });
-            if let Ok(resp) = client.post("https://api.groq.com/openai/v1/chat/completions")
-                .header("Authorization", format!("Bearer {}", groq_api_key)).json(&code_prompt).send().await {
-                    if let Ok(json_resp) = resp.json::<Value>().await {
-                        if let Some(python_code) = json_resp["choices"][0]["message"]["content"].as_str() {
-                            let clean_code = python_code.replace("```python", "").replace("```", "").trim().to_string();
-                            let interpreter_url = "http://code-interpreter:8004/execute";
-                            if let Ok(exec_res) = client.post(interpreter_url).json(&json!({ "code": clean_code })).send().await {
-                                    if let Ok(exec_data) = exec_res.json::<Value>().await {
-                                        let output = exec_data["output"].as_str().unwrap_or("No output").to_string();
-                                        // Final output string for frontend
-                                        let summary = format!("🤖 **I wrote and executed a Python script to calculate this:**\n\nCode:\n```python\n{}\n```\n\nResult:\n```\n{}\n```", clean_code, output);
-                                        return Ok(warp::reply::json(&QueryResponse { request_id, summary, citations: vec![], pending_actions: vec![] }));
-                                    }
-                            }
-                        }
-                    }
-            }
+        let event = serde_json::json!({
+            "action_id": action_id,
+            "status": "pending",
+            "action_type": decision.action_type,
+        }).to_string();
+        if let Err(e) = redis_client.publish(ws::PENDING_ACTIONS_CHANNEL, &event).await {
+            warn!("Failed to publish pending-action event for {}: {}", action_id, e);
         }
     }
 
-    // --- PATH D: STANDARD RAG (Fallback) ---
+    let summary = if outcome.summary.is_empty() && !outcome.decisions.is_empty() {
+        describe_decisions(&outcome.decisions, &pending_action_ids)
+    } else {
+        outcome.summary
+    };
+
+    Ok(warp::reply::json(&QueryResponse {
+        request_id,
+        summary,
+        citations: outcome.citations,
+        pending_actions: pending_action_ids,
+    }))
+}
+
+/// Wires up one agent of each kind, sharing the active LLM client across
+/// the planner, summarizer, and code stage. Built per-request to keep
+/// configuration hot-reloadable, matching how `RetrieverAgent` was already
+/// constructed from env vars on every call.
+fn build_orchestrator(llm_registry: &LlmRegistry) -> Orchestrator {
+    let llm = llm_registry.active_client();
+    let planner = PlannerAgent::new(llm.clone());
+    let summarizer = SummarizerAgent::new(llm.clone());
+    let decision = DecisionAgent::new();
+
+    Orchestrator::new(planner, build_retriever(), summarizer, decision, llm)
+}
+
+/// Shared by the buffered and streaming query paths so both pick up the
+/// same RRF tuning from the environment.
+fn build_retriever() -> RetrieverAgent {
     let embedding_url = env::var("EMBEDDING_SERVICE_URL").unwrap_or("http://embedding-service:8002".to_string());
     let vector_url = env::var("VECTOR_DB_SERVICE_URL").unwrap_or("http://vector-db-service:8003".to_string());
-    let mut context_text = String::new();
-    let mut citations = Vec::new();
-
-    if let Ok(resp) = client.post(format!("{}/embed", embedding_url)).json(&json!({ "texts": [request.query] })).send().await {
-        if let Ok(json_data) = resp.json::<Value>().await {
-            if let Some(vecs) = json_data["embeddings"].as_array() {
-                if let Some(first_vec) = vecs.get(0).and_then(|v| v.as_array()) {
-                    let vector: Vec<f64> = first_vec.iter().map(|n| n.as_f64().unwrap_or(0.0)).collect();
-                    let search_payload = json!({ "query_vector": vector, "query_text": request.query, "top_k": 3, "hybrid": true, "user_id": request.user_id });
-                    if let Ok(s_resp) = client.post(format!("{}/search/hybrid", vector_url)).json(&search_payload).send().await {
-                        if let Ok(results) = s_resp.json::<Value>().await {
-                            if let Some(matches) = results["results"].as_array() {
-                                for m in matches {
-                                    let text = m["metadata"]["text"].as_str().unwrap_or("").to_string();
-                                    let score = m["score"].as_f64().unwrap_or(0.0) as f32;
-                                    if !text.is_empty() {
-                                        context_text.push_str(&format!("- {}\n", text));
-                                        citations.push(Citation { doc_id: Uuid::new_v4(), passage_id: Uuid::new_v4(), page: m["metadata"]["page"].as_i64().map(|v| v as i32), text: text.chars().take(150).collect(), relevance_score: score });
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+    let rrf_k = env::var("RRF_K").ok().and_then(|v| v.parse().ok()).unwrap_or(60.0);
+    let dense_weight = env::var("RRF_DENSE_WEIGHT").ok().and_then(|v| v.parse().ok()).unwrap_or(1.0);
+    let sparse_weight = env::var("RRF_SPARSE_WEIGHT").ok().and_then(|v| v.parse().ok()).unwrap_or(1.0);
+    RetrieverAgent::new(embedding_url, vector_url).with_rrf_params(rrf_k, dense_weight, sparse_weight)
+}
+
+/// Human-readable summary for a query whose plan resolved to actions
+/// rather than a RAG answer, mirroring the per-type messages the old
+/// keyword branches used to return directly.
+fn describe_decisions(decisions: &[ActionDecision], action_ids: &[Uuid]) -> String {
+    decisions
+        .iter()
+        .zip(action_ids.iter())
+        .map(|(decision, action_id)| match decision.action_type.as_str() {
+            "JIRA_TICKET" => format!("✅ Prepared JIRA ticket (Action ID: {})", action_id),
+            "EMAIL_ALERT" => format!("✅ Drafted Email Alert (Action ID: {})", action_id),
+            "SLACK_ALERT" => "✅ I have drafted a Slack message. Check the 'Pending Actions' tab to approve and post it.".to_string(),
+            _ => format!("✅ Prepared action (Action ID: {})", action_id),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QueryStreamParams {
+    /// Set by a client joining a request already in flight (e.g. reconnecting
+    /// after a dropped connection); skips re-running retrieval/summarization
+    /// and just replays the buffered + live events for that request.
+    pub request_id: Option<Uuid>,
+    pub user_id: Option<String>,
+    pub query: Option<String>,
+}
+
+/// Streaming counterpart to `handle_query`: subscribes to a per-request
+/// Redis pub/sub channel that `SummarizerAgent::summarize_streaming`
+/// publishes partial generations to, and relays them to the client as SSE
+/// `token` events, followed by a `citations` event and a `done` (or
+/// `error`) event. A first-time caller kicks off retrieval + streaming
+/// summarization in the background; a caller that passes an existing
+/// `request_id` just joins that request's stream via the `StreamHub`,
+/// which buffers events so a late joiner still gets everything already
+/// produced.
+pub async fn handle_query_stream(
+    params: QueryStreamParams,
+    db_pool: DbPool,
+    redis_client: RedisClient,
+    llm_registry: LlmRegistry,
+    hub: Arc<StreamHub>,
+) -> Result<impl Reply, Rejection> {
+    let request_id = match params.request_id {
+        Some(request_id) => request_id,
+        None => {
+            let query = params.query.clone().unwrap_or_default();
+            let user_id = params.user_id.clone().unwrap_or_else(|| "admin_user".to_string());
+            if query.trim().is_empty() {
+                return Err(warp::reject::not_found());
             }
+
+            let request_id = Uuid::new_v4();
+            info!("Streaming query for User {}: {}", user_id, query);
+
+            let _ = sqlx::query("INSERT INTO requests (id, user_id, query, status, created_at) VALUES ($1, $2, $3, $4, NOW())")
+                .bind(request_id).bind(&user_id).bind(&query).bind("processing").execute(&db_pool).await;
+
+            spawn_redis_relay(redis_client.clone(), hub.clone(), request_id);
+
+            let db_pool = db_pool.clone();
+            let mut redis_client = redis_client.clone();
+            tokio::spawn(async move {
+                run_streaming_query(&db_pool, &mut redis_client, llm_registry, &query, &user_id, request_id).await;
+            });
+
+            request_id
         }
-    }
+    };
+
+    let (buffered, receiver) = hub.subscribe(request_id).await;
+    let reply_stream = futures::stream::iter(buffered.into_iter().map(Ok))
+        .chain(tokio_stream::wrappers::BroadcastStream::new(receiver).filter_map(|event| async { event.ok() }))
+        .map(to_sse_event);
+
+    Ok(warp::sse::reply(warp::sse::keep_alive().stream(reply_stream)))
+}
 
-    if context_text.is_empty() { context_text = "No relevant documents found.".to_string(); }
-
-    let groq_api_key = env::var("GROQ_API_KEY").unwrap_or_default();
-    let mut summary = String::new();
-
-    if !groq_api_key.is_empty() {
-        let llm_body = json!({
-            "model": "llama-3.3-70b-versatile",
-            "messages": [
-                { "role": "system", "content": "You are a helpful Enterprise AI. Use the provided Context to answer." },
-                { "role": "user", "content": format!("Context:\n{}\n\nQuestion: {}", context_text, request.query) }
-            ]
-        });
-        if let Ok(resp) = client.post("https://api.groq.com/openai/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", groq_api_key)).header("Content-Type", "application/json").json(&llm_body).send().await {
-                if let Ok(json_resp) = resp.json::<Value>().await {
-                    summary = json_resp["choices"][0]["message"]["content"].as_str().unwrap_or("Error").to_string();
-                }
+fn to_sse_event(event: StreamEvent) -> Result<warp::sse::Event, std::convert::Infallible> {
+    let sse_event = match event {
+        StreamEvent::Token { text } => warp::sse::Event::default().event("token").data(text),
+        StreamEvent::Citations { citations } => warp::sse::Event::default()
+            .event("citations")
+            .data(serde_json::to_string(&citations).unwrap_or_default()),
+        StreamEvent::Done => warp::sse::Event::default().event("done").data(""),
+        StreamEvent::Error { message } => warp::sse::Event::default().event("error").data(message),
+    };
+    Ok(sse_event)
+}
+
+/// Runs retrieval + streaming summarization for a freshly-created request,
+/// then publishes the terminal `citations`/`done` (or `error`) events to
+/// the same Redis channel the token stream used, so `spawn_redis_relay`
+/// (and therefore every subscribed client) sees one consistent event
+/// sequence. Once a rejection would reach the client mid-stream there's no
+/// HTTP status left to change, so failures here become a terminal SSE
+/// `error` frame instead of going through `error::handle_rejection`.
+async fn run_streaming_query(
+    db_pool: &DbPool,
+    redis_client: &mut RedisClient,
+    llm_registry: LlmRegistry,
+    query: &str,
+    user_id: &str,
+    request_id: Uuid,
+) {
+    let channel = summarizer_channel(request_id);
+    let retrieval = match build_retriever().retrieve(query, 3, user_id).await {
+        Ok(retrieval) => retrieval,
+        Err(e) => {
+            warn!("Retrieval failed for streaming request {}: {}", request_id, e);
+            RetrievalResult { passages: vec![], embeddings: vec![], scores: vec![] }
+        }
+    };
+
+    let summarizer = SummarizerAgent::new(llm_registry.active_client());
+    let outcome = summarizer.summarize_streaming(query, &retrieval, redis_client, request_id).await;
+
+    match outcome {
+        Ok((summary, citations)) => {
+            let _ = redis_client.publish(&channel, &serde_json::json!({"type": "citations", "citations": citations}).to_string()).await;
+            let _ = redis_client.publish(&channel, &serde_json::json!({"type": "done"}).to_string()).await;
+            let _ = sqlx::query("UPDATE requests SET status = $1, completed_at = NOW() WHERE id = $2")
+                .bind("completed").bind(request_id).execute(db_pool).await;
+            info!("Streamed summary for request {} ({} chars)", request_id, summary.len());
+        }
+        Err(e) => {
+            error!("Streaming summarization failed for request {}: {}", request_id, e);
+            let _ = redis_client.publish(&channel, &serde_json::json!({"type": "error", "message": e.to_string()}).to_string()).await;
+            let _ = sqlx::query("UPDATE requests SET status = $1, completed_at = NOW() WHERE id = $2")
+                .bind("failed").bind(request_id).execute(db_pool).await;
         }
     }
+}
+
+/// Subscribes to the per-request Redis channel and relays every message
+/// into the in-process `StreamHub` so any number of SSE clients can watch
+/// the same request without each opening their own Redis subscription.
+/// Exits once a terminal (`done`/`error`) event is relayed.
+fn spawn_redis_relay(redis_client: RedisClient, hub: Arc<StreamHub>, request_id: Uuid) {
+    let channel = summarizer_channel(request_id);
+    tokio::spawn(async move {
+        let mut pubsub = match redis_client.subscribe(&channel).await {
+            Ok(pubsub) => pubsub,
+            Err(e) => {
+                warn!("Failed to subscribe to {}: {}", channel, e);
+                hub.publish(request_id, StreamEvent::Error { message: e.to_string() }).await;
+                return;
+            }
+        };
+        let mut messages = pubsub.on_message();
+
+        while let Some(msg) = messages.next().await {
+            let Ok(payload) = msg.get_payload::<String>() else { continue };
+            let Ok(event) = parse_stream_event(&payload) else { continue };
+            let terminal = event.is_terminal();
+            hub.publish(request_id, event).await;
+            if terminal {
+                break;
+            }
+        }
+    });
+}
 
-    Ok(warp::reply::json(&QueryResponse { request_id, summary, citations, pending_actions: vec![] }))
+fn parse_stream_event(payload: &str) -> Result<StreamEvent, serde_json::Error> {
+    serde_json::from_str(payload)
 }
\ No newline at end of file
@@ -1,24 +1,74 @@
 use warp::{Rejection, Reply};
-use warp::http::StatusCode;
+use warp::http::{Response, StatusCode};
 use crate::db::DbPool;
 use crate::models::Document;
-use crate::error::ApiError;
+use crate::store::Store;
 use uuid::Uuid;
-use tracing::{info, error};
+use std::sync::Arc;
+use tracing::{info, error, warn};
 
 pub async fn handle_get_source(
     doc_id: Uuid,
+    range_header: Option<String>,
     db_pool: DbPool,
+    doc_store: Arc<dyn Store>,
 ) -> Result<impl Reply, Rejection> {
     info!("Fetching source document: {}", doc_id);
-    
-    // TODO: Query document from database
-    // TODO: Retrieve file from storage
-    // TODO: Return file with metadata
-    
-    // Placeholder until storage integration is implemented
-    Ok(warp::reply::with_status(
-        "source not found",
-        StatusCode::NOT_FOUND,
-    ))
+
+    let document = match sqlx::query_as::<_, Document>(
+        "SELECT id, filename, content_type, s3_key, upload_time, user_id, metadata FROM documents WHERE id = $1",
+    )
+    .bind(doc_id)
+    .fetch_optional(&db_pool)
+    .await
+    {
+        Ok(Some(document)) => document,
+        Ok(None) => return Ok(not_found()),
+        Err(e) => {
+            error!("Failed to fetch document {}: {}", doc_id, e);
+            return Ok(warp::reply::with_status("internal error".to_string(), StatusCode::INTERNAL_SERVER_ERROR).into_response());
+        }
+    };
+
+    let range = range_header.as_deref().and_then(parse_range);
+
+    let object = match doc_store.get(&document.s3_key, range).await {
+        Ok(object) => object,
+        Err(e) => {
+            warn!("Failed to read stored document {}: {}", doc_id, e);
+            return Ok(not_found());
+        }
+    };
+
+    let mut builder = Response::builder()
+        .header("Content-Type", document.content_type)
+        .header("Accept-Ranges", "bytes");
+
+    builder = match range {
+        Some((start, _)) => builder.status(StatusCode::PARTIAL_CONTENT).header(
+            "Content-Range",
+            format!("bytes {}-{}/{}", start, start + object.bytes.len() as u64 - 1, object.total_len),
+        ),
+        None => builder.status(StatusCode::OK),
+    };
+
+    match builder.body(object.bytes) {
+        Ok(response) => Ok(response.into_response()),
+        Err(_) => Ok(not_found()),
+    }
+}
+
+fn not_found() -> warp::reply::Response {
+    warp::reply::with_status("source not found".to_string(), StatusCode::NOT_FOUND).into_response()
+}
+
+/// Parses a single-range `Range: bytes=start-end` header (no end means "to
+/// EOF"). Multi-range requests aren't supported; callers then fall back to
+/// returning the full body.
+fn parse_range(header: &str) -> Option<(u64, Option<u64>)> {
+    let spec = header.strip_prefix("bytes=")?;
+    let (start, end) = spec.split_once('-')?;
+    let start: u64 = start.parse().ok()?;
+    let end: Option<u64> = if end.is_empty() { None } else { Some(end.parse().ok()?) };
+    Some((start, end))
 }
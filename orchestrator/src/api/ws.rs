@@ -0,0 +1,53 @@
+// WebSocket endpoint relaying pending-action lifecycle events so the UI's
+// "Pending Actions" tab updates live instead of polling `/api/v1/pending`.
+
+use crate::redis_client::RedisClient;
+use futures::StreamExt;
+use tracing::{info, warn};
+use warp::ws::{Message, WebSocket};
+
+/// Redis channel `handle_query` and `handle_approve` publish
+/// `{action_id, status, action_type}` events to.
+pub(crate) const PENDING_ACTIONS_CHANNEL: &str = "pending_actions:updates";
+
+pub async fn handle_socket(socket: WebSocket, redis_client: RedisClient) {
+    let (mut ws_tx, mut ws_rx) = socket.split();
+
+    let mut pubsub = match redis_client.subscribe(PENDING_ACTIONS_CHANNEL).await {
+        Ok(pubsub) => pubsub,
+        Err(e) => {
+            warn!("Failed to subscribe to {}: {}", PENDING_ACTIONS_CHANNEL, e);
+            let _ = ws_tx.send(Message::close()).await;
+            return;
+        }
+    };
+    let mut message_stream = pubsub.on_message();
+
+    loop {
+        tokio::select! {
+            msg = message_stream.next() => {
+                match msg {
+                    Some(msg) => {
+                        let payload = match msg.get_payload::<String>() {
+                            Ok(payload) => payload,
+                            Err(e) => { warn!("Malformed pub/sub payload: {}", e); continue; }
+                        };
+                        if ws_tx.send(Message::text(payload)).await.is_err() {
+                            break;
+                        }
+                    }
+                    None => break,
+                }
+            }
+            incoming = ws_rx.next() => {
+                match incoming {
+                    Some(Ok(m)) if m.is_close() => break,
+                    Some(Ok(_)) => {}
+                    _ => break,
+                }
+            }
+        }
+    }
+
+    info!("Pending-actions WebSocket connection closed");
+}
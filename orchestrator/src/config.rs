@@ -1,5 +1,8 @@
+use crate::llm::LlmProviderConfig;
+use crate::store::StorageConfig;
 use serde::Deserialize;
 use anyhow::Result;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
@@ -12,12 +15,18 @@ pub struct Config {
     pub embedding_service_url: String,
     pub vector_db_service_url: String,
     pub log_level: String,
+    pub llm_providers: HashMap<String, LlmProviderConfig>,
+    pub llm_active_provider: String,
+    pub rrf_k: f32,
+    pub rrf_dense_weight: f32,
+    pub rrf_sparse_weight: f32,
+    pub storage: StorageConfig,
 }
 
 impl Config {
     pub fn from_env() -> Result<Self> {
         dotenvy::dotenv().ok();
-        
+
         Ok(Config {
             port: std::env::var("PORT")
                 .unwrap_or_else(|_| "8080".to_string())
@@ -37,6 +46,56 @@ impl Config {
                 .unwrap_or_else(|_| "http://localhost:8003".to_string()),
             log_level: std::env::var("LOG_LEVEL")
                 .unwrap_or_else(|_| "info".to_string()),
+            llm_providers: Self::load_llm_providers()?,
+            llm_active_provider: std::env::var("LLM_ACTIVE_PROVIDER")
+                .unwrap_or_else(|_| "groq".to_string()),
+            rrf_k: std::env::var("RRF_K")
+                .ok().and_then(|v| v.parse().ok()).unwrap_or(60.0),
+            rrf_dense_weight: std::env::var("RRF_DENSE_WEIGHT")
+                .ok().and_then(|v| v.parse().ok()).unwrap_or(1.0),
+            rrf_sparse_weight: std::env::var("RRF_SPARSE_WEIGHT")
+                .ok().and_then(|v| v.parse().ok()).unwrap_or(1.0),
+            storage: Self::load_storage_config()?,
+        })
+    }
+
+    /// Storage backend is configured as a JSON object (`{ "type": "filesystem" | "s3", ... }`)
+    /// in `STORAGE_CONFIG` (a path to a JSON file) so a deployment can move
+    /// from local disk to S3 without a recompile. Falls back to a
+    /// filesystem store under `./data/documents` for local/dev setups that
+    /// predate the config file.
+    fn load_storage_config() -> Result<StorageConfig> {
+        if let Ok(path) = std::env::var("STORAGE_CONFIG") {
+            let contents = std::fs::read_to_string(&path)?;
+            let storage: StorageConfig = serde_json::from_str(&contents)?;
+            return Ok(storage);
+        }
+
+        Ok(StorageConfig::Filesystem {
+            base_dir: std::env::var("STORAGE_BASE_DIR").unwrap_or_else(|_| "./data/documents".to_string()),
         })
     }
+
+    /// Providers are configured as a JSON object of `name -> { "type": ..., ... }`
+    /// in `LLM_PROVIDERS_CONFIG` (a path to a JSON file) so
+    /// operators can add/swap backends without recompiling. Falls back to a
+    /// single Groq entry built from `GROQ_API_KEY` for local/dev setups that
+    /// predate the config file.
+    fn load_llm_providers() -> Result<HashMap<String, LlmProviderConfig>> {
+        if let Ok(path) = std::env::var("LLM_PROVIDERS_CONFIG") {
+            let contents = std::fs::read_to_string(&path)?;
+            let providers: HashMap<String, LlmProviderConfig> = serde_json::from_str(&contents)?;
+            return Ok(providers);
+        }
+
+        let mut providers = HashMap::new();
+        providers.insert(
+            "groq".to_string(),
+            LlmProviderConfig::Groq {
+                api_key: std::env::var("GROQ_API_KEY").unwrap_or_default(),
+                model: "llama-3.3-70b-versatile".to_string(),
+            },
+        );
+        Ok(providers)
+    }
 }
@@ -33,6 +33,11 @@ pub enum ApiError {
 
 impl Reject for ApiError {}
 
+/// Maps a rejection to an HTTP status + JSON body. Only applies before a
+/// response is committed — once `query::handle_query_stream`'s SSE body has
+/// started, there's no status left to change, so failures discovered mid-stream
+/// are instead emitted as a terminal SSE `error` event from within the stream
+/// itself rather than routed through here.
 pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Rejection> {
     if let Some(api_err) = err.find::<ApiError>() {
         let (code, message) = match api_err {
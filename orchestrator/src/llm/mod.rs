@@ -0,0 +1,44 @@
+// LLM provider abstraction: a common `LlmClient` trait plus a config-driven
+// registry so operators can switch providers (OpenAI, Groq, Anthropic,
+// Ollama-style local endpoints) without recompiling.
+
+mod providers;
+mod registry;
+
+pub use providers::{AnthropicClient, GroqClient, OllamaClient, OpenAiClient};
+pub use registry::{LlmProviderConfig, LlmRegistry};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self { role: "system".to_string(), content: content.into() }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self { role: "user".to_string(), content: content.into() }
+    }
+}
+
+/// Common interface every LLM backend implements, so the query handler,
+/// `PlannerAgent`, and `SummarizerAgent` can all depend on one trait object
+/// instead of hand-rolling `reqwest` calls against a specific provider.
+#[async_trait]
+pub trait LlmClient: Send + Sync {
+    async fn chat(&self, messages: Vec<ChatMessage>) -> Result<String>;
+
+    /// Same call, but as a stream of delta tokens as they're generated
+    /// (`stream: true` on the wire for backends that support it). Backends
+    /// without native token streaming fall back to a single-item stream
+    /// carrying the full response.
+    async fn chat_stream(&self, messages: Vec<ChatMessage>) -> Result<BoxStream<'static, Result<String>>>;
+}
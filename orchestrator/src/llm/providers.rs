@@ -0,0 +1,234 @@
+// One `LlmClient` implementation per backend. Each holds only what it needs
+// to issue its own chat-completions request; the registry is what picks
+// which one is active.
+
+use super::{ChatMessage, LlmClient};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use serde_json::{json, Value};
+
+pub struct GroqClient {
+    http: reqwest::Client,
+    api_key: String,
+    model: String,
+}
+
+impl GroqClient {
+    pub fn new(api_key: String, model: String) -> Self {
+        Self { http: reqwest::Client::new(), api_key, model }
+    }
+}
+
+#[async_trait]
+impl LlmClient for GroqClient {
+    async fn chat(&self, messages: Vec<ChatMessage>) -> Result<String> {
+        let body = json!({ "model": self.model, "messages": messages });
+        let resp = self
+            .http
+            .post("https://api.groq.com/openai/v1/chat/completions")
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .json(&body)
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+        extract_choice(&resp)
+    }
+
+    async fn chat_stream(&self, messages: Vec<ChatMessage>) -> Result<BoxStream<'static, Result<String>>> {
+        let body = json!({ "model": self.model, "messages": messages, "stream": true });
+        stream_openai_compatible(
+            self.http.clone(),
+            "https://api.groq.com/openai/v1/chat/completions".to_string(),
+            vec![("Authorization".to_string(), format!("Bearer {}", self.api_key))],
+            body,
+        )
+        .await
+    }
+}
+
+pub struct OpenAiClient {
+    http: reqwest::Client,
+    api_key: String,
+    model: String,
+}
+
+impl OpenAiClient {
+    pub fn new(api_key: String, model: String) -> Self {
+        Self { http: reqwest::Client::new(), api_key, model }
+    }
+}
+
+#[async_trait]
+impl LlmClient for OpenAiClient {
+    async fn chat(&self, messages: Vec<ChatMessage>) -> Result<String> {
+        let body = json!({ "model": self.model, "messages": messages });
+        let resp = self
+            .http
+            .post("https://api.openai.com/v1/chat/completions")
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .json(&body)
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+        extract_choice(&resp)
+    }
+
+    async fn chat_stream(&self, messages: Vec<ChatMessage>) -> Result<BoxStream<'static, Result<String>>> {
+        let body = json!({ "model": self.model, "messages": messages, "stream": true });
+        stream_openai_compatible(
+            self.http.clone(),
+            "https://api.openai.com/v1/chat/completions".to_string(),
+            vec![("Authorization".to_string(), format!("Bearer {}", self.api_key))],
+            body,
+        )
+        .await
+    }
+}
+
+pub struct AnthropicClient {
+    http: reqwest::Client,
+    api_key: String,
+    model: String,
+}
+
+impl AnthropicClient {
+    pub fn new(api_key: String, model: String) -> Self {
+        Self { http: reqwest::Client::new(), api_key, model }
+    }
+}
+
+#[async_trait]
+impl LlmClient for AnthropicClient {
+    async fn chat(&self, messages: Vec<ChatMessage>) -> Result<String> {
+        let (system, turns): (Vec<_>, Vec<_>) =
+            messages.into_iter().partition(|m| m.role == "system");
+        let system_prompt = system.into_iter().map(|m| m.content).collect::<Vec<_>>().join("\n");
+
+        let body = json!({
+            "model": self.model,
+            "max_tokens": 1024,
+            "system": system_prompt,
+            "messages": turns,
+        });
+        let resp = self
+            .http
+            .post("https://api.anthropic.com/v1/messages")
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01")
+            .json(&body)
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+
+        resp["content"][0]["text"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow!("Anthropic response missing content: {}", resp))
+    }
+
+    // Anthropic's native event stream uses a different frame shape than the
+    // OpenAI-compatible backends; until it's wired up, fall back to a single
+    // chunk carrying the full response rather than leaving callers blocked.
+    async fn chat_stream(&self, messages: Vec<ChatMessage>) -> Result<BoxStream<'static, Result<String>>> {
+        let full = self.chat(messages).await;
+        Ok(stream::once(async move { full }).boxed())
+    }
+}
+
+/// A local/Ollama-style endpoint: any server that speaks the same
+/// `{model, messages}` -> `{message: {content}}` chat shape, no API key.
+pub struct OllamaClient {
+    http: reqwest::Client,
+    endpoint: String,
+    model: String,
+}
+
+impl OllamaClient {
+    pub fn new(endpoint: String, model: String) -> Self {
+        Self { http: reqwest::Client::new(), endpoint, model }
+    }
+}
+
+#[async_trait]
+impl LlmClient for OllamaClient {
+    async fn chat(&self, messages: Vec<ChatMessage>) -> Result<String> {
+        let body = json!({ "model": self.model, "messages": messages, "stream": false });
+        let resp = self
+            .http
+            .post(format!("{}/api/chat", self.endpoint))
+            .json(&body)
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+
+        resp["message"]["content"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow!("Ollama response missing content: {}", resp))
+    }
+
+    async fn chat_stream(&self, messages: Vec<ChatMessage>) -> Result<BoxStream<'static, Result<String>>> {
+        let full = self.chat(messages).await;
+        Ok(stream::once(async move { full }).boxed())
+    }
+}
+
+fn extract_choice(resp: &Value) -> Result<String> {
+    resp["choices"][0]["message"]["content"]
+        .as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| anyhow!("LLM response missing content: {}", resp))
+}
+
+/// Opens a streaming chat-completions request against an OpenAI-compatible
+/// endpoint (Groq, OpenAI) and turns the `data: {...}` SSE frames into a
+/// stream of delta-content tokens, skipping the `data: [DONE]` sentinel; the
+/// stream ends when the response body does.
+async fn stream_openai_compatible(
+    http: reqwest::Client,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Value,
+) -> Result<BoxStream<'static, Result<String>>> {
+    let mut req = http.post(url).json(&body);
+    for (key, value) in headers {
+        req = req.header(key, value);
+    }
+    let response = req.send().await?;
+
+    let byte_stream = response.bytes_stream();
+    let token_stream = byte_stream
+        .map(|chunk| chunk.map_err(anyhow::Error::from))
+        .flat_map(|chunk| {
+            let tokens = match chunk {
+                Ok(bytes) => parse_sse_deltas(&bytes),
+                Err(e) => vec![Err(e)],
+            };
+            stream::iter(tokens)
+        })
+        .filter_map(|item| async move { item.transpose() });
+
+    Ok(token_stream.boxed())
+}
+
+/// Parses one chunk of raw SSE bytes into zero or more delta-content tokens.
+/// Returns `Ok(None)` for frames that carry no content (e.g. `[DONE]`) so
+/// the caller can filter them out without ending the stream early.
+fn parse_sse_deltas(bytes: &[u8]) -> Vec<Result<Option<String>>> {
+    let text = String::from_utf8_lossy(bytes);
+    text.lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .map(|payload| {
+            if payload.trim() == "[DONE]" {
+                return Ok(None);
+            }
+            let parsed: Value = serde_json::from_str(payload)?;
+            Ok(parsed["choices"][0]["delta"]["content"].as_str().map(|s| s.to_string()))
+        })
+        .collect()
+}
@@ -0,0 +1,69 @@
+use super::{AnthropicClient, GroqClient, LlmClient, OllamaClient, OpenAiClient};
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Typed, tagged config for a single provider entry, e.g.
+/// `{ "type": "groq", "api_key": "...", "model": "llama-3.3-70b-versatile" }`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LlmProviderConfig {
+    Groq { api_key: String, model: String },
+    Openai { api_key: String, model: String },
+    Anthropic { api_key: String, model: String },
+    Ollama { endpoint: String, model: String },
+}
+
+impl LlmProviderConfig {
+    fn build(&self) -> Arc<dyn LlmClient> {
+        match self {
+            LlmProviderConfig::Groq { api_key, model } => {
+                Arc::new(GroqClient::new(api_key.clone(), model.clone()))
+            }
+            LlmProviderConfig::Openai { api_key, model } => {
+                Arc::new(OpenAiClient::new(api_key.clone(), model.clone()))
+            }
+            LlmProviderConfig::Anthropic { api_key, model } => {
+                Arc::new(AnthropicClient::new(api_key.clone(), model.clone()))
+            }
+            LlmProviderConfig::Ollama { endpoint, model } => {
+                Arc::new(OllamaClient::new(endpoint.clone(), model.clone()))
+            }
+        }
+    }
+}
+
+/// Instantiates every configured provider once at startup and hands out the
+/// active one (by name) to the query handler, `PlannerAgent`, and the
+/// math/code path, replacing the old hardcoded `reqwest` blocks.
+#[derive(Clone)]
+pub struct LlmRegistry {
+    clients: HashMap<String, Arc<dyn LlmClient>>,
+    active: String,
+}
+
+impl LlmRegistry {
+    pub fn new(providers: HashMap<String, LlmProviderConfig>, active: String) -> Result<Self> {
+        if !providers.contains_key(&active) {
+            return Err(anyhow!("active LLM provider '{}' is not configured", active));
+        }
+        let clients = providers.into_iter().map(|(name, cfg)| (name, cfg.build())).collect();
+        Ok(Self { clients, active })
+    }
+
+    /// The client configured as `active`, used by default everywhere a
+    /// single `LlmClient` is expected.
+    pub fn active_client(&self) -> Arc<dyn LlmClient> {
+        self.clients
+            .get(&self.active)
+            .cloned()
+            .expect("active provider validated at construction")
+    }
+
+    /// Look up a specific provider by name, for callers that want to target
+    /// something other than the default (e.g. a cheaper model for routing).
+    pub fn client(&self, name: &str) -> Option<Arc<dyn LlmClient>> {
+        self.clients.get(name).cloned()
+    }
+}
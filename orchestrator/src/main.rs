@@ -6,9 +6,17 @@ mod api;
 mod config;
 mod db;
 mod error;
+mod llm;
 mod middleware;
 mod models;
+mod orchestration;
+mod queue;
 mod redis_client;
+mod sanitize;
+mod store;
+mod stream_hub;
+mod webauthn;
+mod worker;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -41,8 +49,31 @@ async fn main() -> anyhow::Result<()> {
     let redis_client = redis_client::RedisClient::new(&config.redis_url).await?;
     info!("Redis connection established");
 
+    // Build the LLM provider registry from config
+    let llm_registry = llm::LlmRegistry::new(
+        config.llm_providers.clone(),
+        config.llm_active_provider.clone(),
+    )?;
+    info!("LLM registry initialized with active provider: {}", config.llm_active_provider);
+
+    // Build the document storage backend (filesystem or S3)
+    let doc_store = config.storage.build()?;
+    info!("Document storage backend initialized");
+
+    // Start the background worker that re-drains approved-but-not-executed
+    // actions left behind by a crash, so execution stays crash-safe.
+    worker::spawn_action_recovery(db_pool.clone());
+
+    // Start the background worker that drives queued ingestion jobs through
+    // extract -> embed -> index, so uploads don't block on the pipeline.
+    queue::spawn_ingestion_worker(db_pool.clone());
+
     // Build API routes
-    let api_routes = api::routes(db_pool.clone(), redis_client.clone())
+    let stream_hub = stream_hub::StreamHub::new();
+    let webauthn_ctx = std::sync::Arc::new(webauthn::WebauthnCtx::new()?);
+    info!("WebAuthn relying party initialized");
+
+    let api_routes = api::routes(db_pool.clone(), redis_client.clone(), llm_registry, doc_store, stream_hub, webauthn_ctx)
         .with(warp::log("api"))
         .with(middleware::cors());
 
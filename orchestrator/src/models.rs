@@ -13,7 +13,7 @@ pub struct Document {
     pub metadata: serde_json::Value,
 }
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Passage {
     pub id: Uuid,
     pub doc_id: Uuid,
@@ -25,7 +25,7 @@ pub struct Passage {
     pub metadata: serde_json::Value,
 }
 
-#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct EmbeddingMeta {
     pub id: Uuid,
     pub passage_id: Uuid,
@@ -77,6 +77,23 @@ pub struct AuditLog {
     pub details: serde_json::Value,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct IngestionJob {
+    pub id: Uuid,
+    pub document_id: Uuid,
+    pub filename: String,
+    pub content_type: String,
+    pub user_id: String,
+    pub status: String,
+    pub passages_total: i32,
+    pub passages_done: i32,
+    pub passages_failed: i32,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
 // API Request/Response models
 #[derive(Debug, Deserialize)]
 pub struct IngestRequest {
@@ -85,9 +102,10 @@ pub struct IngestRequest {
 
 #[derive(Debug, Serialize)]
 pub struct IngestResponse {
+    pub job_id: Uuid,
     pub document_id: Uuid,
     pub filename: String,
-    pub passages_count: usize,
+    pub status: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -113,13 +131,6 @@ pub struct Citation {
     pub relevance_score: f32,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct ApprovalRequest {
-    pub action_id: Uuid,
-    pub approved: bool,
-    pub user_signature: String,
-}
-
 #[derive(Debug, Serialize)]
 pub struct ApprovalResponse {
     pub action_id: Uuid,
@@ -135,7 +146,7 @@ pub struct PlannerStep {
     pub args: serde_json::Value,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RetrievalResult {
     pub passages: Vec<Passage>,
     pub embeddings: Vec<EmbeddingMeta>,
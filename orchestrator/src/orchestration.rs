@@ -0,0 +1,134 @@
+// Orchestration pipeline: drives a query through Planner -> Retriever ->
+// Summarizer/Decision as composable stages instead of a keyword if/else
+// chain. `PlannerAgent::plan` decides which downstream stages run; adding a
+// new action type is a matter of handling a new `PlannerStep::action` here
+// rather than editing the query handler.
+
+use crate::agents::decision::{ActionDecision, DecisionAgent};
+use crate::agents::planner::PlannerAgent;
+use crate::agents::retriever::RetrieverAgent;
+use crate::agents::summarizer::SummarizerAgent;
+use crate::llm::{ChatMessage, LlmClient};
+use crate::models::{Citation, RetrievalResult};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tracing::info;
+
+pub struct OrchestrationOutcome {
+    pub summary: String,
+    pub citations: Vec<Citation>,
+    pub decisions: Vec<ActionDecision>,
+}
+
+pub struct Orchestrator {
+    planner: PlannerAgent,
+    retriever: RetrieverAgent,
+    summarizer: SummarizerAgent,
+    decision: DecisionAgent,
+    llm: Arc<dyn LlmClient>,
+}
+
+impl Orchestrator {
+    pub fn new(
+        planner: PlannerAgent,
+        retriever: RetrieverAgent,
+        summarizer: SummarizerAgent,
+        decision: DecisionAgent,
+        llm: Arc<dyn LlmClient>,
+    ) -> Self {
+        Self { planner, retriever, summarizer, decision, llm }
+    }
+
+    pub async fn run(&self, query: &str, user_id: &str) -> Result<OrchestrationOutcome> {
+        let steps = self.planner.plan(query).await?;
+        info!("Orchestrator: planner produced {} step(s)", steps.len());
+
+        let mut retrieval: Option<RetrievalResult> = None;
+        let mut summary = String::new();
+        let mut decisions = Vec::new();
+
+        for step in &steps {
+            match step.action.as_str() {
+                "retrieve" => {
+                    let retrieval_query = step.args["query"].as_str().unwrap_or(query);
+                    retrieval = Some(self.retriever.retrieve(retrieval_query, 3, user_id).await?);
+                }
+                "summarize" => {
+                    let context = retrieval.clone().unwrap_or(RetrievalResult {
+                        passages: vec![],
+                        embeddings: vec![],
+                        scores: vec![],
+                    });
+                    let (s, _) = self.summarizer.summarize(query, &context).await?;
+                    summary = s;
+                }
+                "decide" => {
+                    decisions = self.decision.decide(&summary, query).await?;
+                }
+                "code" => {
+                    summary = self.run_code_step(query).await;
+                }
+                other => {
+                    info!("Orchestrator: no stage registered for planner action '{}', skipping", other);
+                }
+            }
+        }
+
+        let citations = retrieval.map(|r| build_citations(&r)).unwrap_or_default();
+
+        Ok(OrchestrationOutcome { summary, citations, decisions })
+    }
+
+    /// Sandboxed code execution: asks the LLM for Python, runs it through
+    /// the code-interpreter service, and formats the result for display.
+    async fn run_code_step(&self, query: &str) -> String {
+        let messages = vec![
+            ChatMessage::system("You are a Python Coding Assistant. Output ONLY valid Python code to solve the user's problem. Use 'print()' to output the final answer."),
+            ChatMessage::user(query.to_string()),
+        ];
+
+        let python_code = match self.llm.chat(messages).await {
+            Ok(code) => code,
+            Err(e) => return format!("Error: code generation failed ({})", e),
+        };
+        let clean_code = python_code.replace("```python", "").replace("```", "").trim().to_string();
+
+        let client = reqwest::Client::new();
+        let exec_res = match client
+            .post("http://code-interpreter:8004/execute")
+            .json(&json!({ "code": clean_code }))
+            .send()
+            .await
+        {
+            Ok(res) => res,
+            Err(_) => return "Error: code interpreter unavailable".to_string(),
+        };
+
+        match exec_res.json::<Value>().await {
+            Ok(data) => {
+                let output = data["output"].as_str().unwrap_or("No output");
+                format!(
+                    "🤖 **I wrote and executed a Python script to calculate this:**\n\nCode:\n```python\n{}\n```\n\nResult:\n```\n{}\n```",
+                    clean_code, output
+                )
+            }
+            Err(_) => "Error: could not parse interpreter output".to_string(),
+        }
+    }
+}
+
+fn build_citations(context: &RetrievalResult) -> Vec<Citation> {
+    context
+        .passages
+        .iter()
+        .zip(context.scores.iter())
+        .map(|(passage, score)| Citation {
+            doc_id: passage.doc_id,
+            passage_id: passage.id,
+            page: passage.page_num,
+            text: passage.text.chars().take(150).collect(),
+            relevance_score: *score,
+        })
+        .collect()
+}
@@ -0,0 +1,414 @@
+// Durable ingestion job queue: `handle_ingest` enqueues a row and returns
+// immediately; `spawn_ingestion_worker` drains queued/retryable jobs in the
+// background so a slow extract/embed/index round trip no longer ties up an
+// HTTP request, and a mid-job crash doesn't lose the upload.
+
+use crate::models::IngestionJob;
+use crate::db::DbPool;
+use crate::sanitize::{self, SanitizedPassage};
+use anyhow::Result;
+use rand::Rng;
+use serde_json::{json, Value};
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+const MAX_JOB_ATTEMPTS: i32 = 3;
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const CHUNK_SIZE: usize = 50;
+const MAX_CHUNK_ATTEMPTS: u32 = 3;
+const DEFAULT_EMBED_CONCURRENCY: usize = 4;
+
+/// Row shape used only inside the worker, kept separate from the public
+/// `IngestionJob` status model so `GET /api/v1/status/{id}` never has to
+/// worry about accidentally serializing the raw file bytes.
+#[derive(sqlx::FromRow)]
+struct IngestionJobRow {
+    id: Uuid,
+    document_id: Uuid,
+    filename: String,
+    content_type: String,
+    user_id: String,
+    file_bytes: Vec<u8>,
+    attempts: i32,
+}
+
+/// Storage key a document's original file is persisted under via `Store`,
+/// shared by `handle_ingest` (which writes it) and `run_pipeline` (which
+/// records it on the `documents` row so `sources::handle_get_source` can
+/// read it back).
+pub fn storage_key_for(document_id: Uuid, filename: &str) -> String {
+    format!("{}/{}", document_id, filename)
+}
+
+pub async fn enqueue(
+    db_pool: &DbPool,
+    document_id: Uuid,
+    filename: &str,
+    content_type: &str,
+    user_id: &str,
+    file_bytes: Vec<u8>,
+) -> Result<Uuid> {
+    let job_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO ingestion_jobs (id, document_id, filename, content_type, user_id, file_bytes, status, created_at, updated_at) \
+         VALUES ($1, $2, $3, $4, $5, $6, 'queued', NOW(), NOW())",
+    )
+    .bind(job_id)
+    .bind(document_id)
+    .bind(filename)
+    .bind(content_type)
+    .bind(user_id)
+    .bind(file_bytes)
+    .execute(db_pool)
+    .await?;
+    Ok(job_id)
+}
+
+pub async fn status(db_pool: &DbPool, job_id: Uuid) -> Result<Option<IngestionJob>> {
+    let job = sqlx::query_as::<_, IngestionJob>(
+        "SELECT id, document_id, filename, content_type, user_id, status, passages_total, passages_done, passages_failed, attempts, last_error, created_at, updated_at \
+         FROM ingestion_jobs WHERE id = $1",
+    )
+    .bind(job_id)
+    .fetch_optional(db_pool)
+    .await?;
+    Ok(job)
+}
+
+/// Background worker that claims queued (or previously-failed, under-attempt)
+/// jobs and drives them through the ingestion pipeline, one at a time.
+pub fn spawn_ingestion_worker(db_pool: DbPool) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = drain_once(&db_pool).await {
+                error!("Ingestion worker iteration failed: {}", e);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+async fn drain_once(db_pool: &DbPool) -> Result<()> {
+    let jobs = sqlx::query_as::<_, IngestionJobRow>(
+        "SELECT id, document_id, filename, content_type, user_id, file_bytes, attempts FROM ingestion_jobs \
+         WHERE status = 'queued' AND attempts < $1 ORDER BY created_at ASC LIMIT 5",
+    )
+    .bind(MAX_JOB_ATTEMPTS)
+    .fetch_all(db_pool)
+    .await?;
+
+    for job in jobs {
+        process_job(db_pool, job).await;
+    }
+    Ok(())
+}
+
+async fn process_job(db_pool: &DbPool, job: IngestionJobRow) {
+    let attempt = job.attempts + 1;
+    let _ = sqlx::query("UPDATE ingestion_jobs SET status = 'processing', attempts = $1, updated_at = NOW() WHERE id = $2")
+        .bind(attempt)
+        .bind(job.id)
+        .execute(db_pool)
+        .await;
+
+    match run_pipeline(db_pool, &job).await {
+        Ok(()) => {
+            let _ = sqlx::query("UPDATE ingestion_jobs SET status = 'completed', updated_at = NOW() WHERE id = $1")
+                .bind(job.id)
+                .execute(db_pool)
+                .await;
+            info!("Ingestion job {} completed", job.id);
+        }
+        Err(e) => {
+            warn!("Ingestion job {} attempt {}/{} failed: {}", job.id, attempt, MAX_JOB_ATTEMPTS, e);
+            let next_status = if attempt >= MAX_JOB_ATTEMPTS { "failed" } else { "queued" };
+            let _ = sqlx::query("UPDATE ingestion_jobs SET status = $1, last_error = $2, updated_at = NOW() WHERE id = $3")
+                .bind(next_status)
+                .bind(e.to_string())
+                .bind(job.id)
+                .execute(db_pool)
+                .await;
+        }
+    }
+}
+
+/// Extracts passages, then embeds/indexes whichever ones don't already have
+/// a `passages` row for this document, so a retried job resumes instead of
+/// re-embedding passages a previous attempt already persisted.
+async fn run_pipeline(db_pool: &DbPool, job: &IngestionJobRow) -> Result<()> {
+    let client = reqwest::Client::new();
+    let ingest_url = env::var("INGESTION_SERVICE_URL").unwrap_or("http://ingestion-service:8001".to_string());
+    let embed_url = env::var("EMBEDDING_SERVICE_URL").unwrap_or("http://embedding-service:8002".to_string());
+    let vector_url = env::var("VECTOR_DB_SERVICE_URL").unwrap_or("http://vector-db-service:8003".to_string());
+
+    let part = reqwest::multipart::Part::bytes(job.file_bytes.clone())
+        .file_name(job.filename.clone())
+        .mime_str(&job.content_type)?;
+    let multipart_form = reqwest::multipart::Form::new().part("file", part);
+    let extraction: Value = client
+        .post(format!("{}/extract", ingest_url))
+        .multipart(multipart_form)
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    let passages = extraction["passages"].as_array().cloned().unwrap_or_default();
+    let total_chars = extraction["total_chars"].as_u64().unwrap_or(0);
+
+    sqlx::query(
+        "INSERT INTO documents (id, filename, content_type, s3_key, upload_time, user_id, metadata) VALUES ($1, $2, $3, $4, NOW(), $5, $6) \
+         ON CONFLICT (id) DO NOTHING",
+    )
+    .bind(job.document_id)
+    .bind(&job.filename)
+    .bind(&job.content_type)
+    .bind(storage_key_for(job.document_id, &job.filename))
+    .bind(&job.user_id)
+    .bind(json!({ "total_chars": total_chars as i64 }))
+    .execute(db_pool)
+    .await?;
+
+    sqlx::query("UPDATE ingestion_jobs SET passages_total = $1, updated_at = NOW() WHERE id = $2")
+        .bind(passages.len() as i32)
+        .bind(job.id)
+        .execute(db_pool)
+        .await?;
+
+    // Chunks are embedded/indexed concurrently below, so a retried job can't
+    // assume the first N passages are the ones already done (an earlier
+    // attempt may have completed chunk 3 before chunk 1); resume by excluding
+    // whichever `passage_index`es already have a row instead of a raw count.
+    let already_indexed: std::collections::HashSet<i32> = sqlx::query_scalar::<_, i32>(
+        "SELECT passage_index FROM passages WHERE doc_id = $1",
+    )
+    .bind(job.document_id)
+    .fetch_all(db_pool)
+    .await
+    .unwrap_or_default()
+    .into_iter()
+    .collect();
+
+    // Sanitize before anything else touches the extracted text: strips
+    // markup/zero-width tricks and flags passages that read like injected
+    // directives, so both the embedding input and the stored passage carry
+    // the cleaned text rather than the raw extraction.
+    let remaining: Vec<(usize, SanitizedPassage, Option<i64>)> = passages
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !already_indexed.contains(&(*index as i32)))
+        .map(|(index, p)| (index, sanitize::sanitize_passage(p["text"].as_str().unwrap_or("")), p["page"].as_i64()))
+        .filter(|(_, sanitized, _)| !sanitized.text.trim().is_empty())
+        .collect();
+
+    // Chunks are embedded/indexed concurrently (bounded by a semaphore, not
+    // one-at-a-time) since they're independent of each other; each chunk
+    // retries on its own with jittered backoff so one flaky embedding call
+    // no longer silently drops every passage behind it.
+    let max_concurrency: usize = env::var("EMBED_MAX_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_EMBED_CONCURRENCY);
+    let semaphore = Arc::new(Semaphore::new(max_concurrency));
+
+    let mut handles = Vec::new();
+    for chunk in remaining.chunks(CHUNK_SIZE).map(|c| c.to_vec()) {
+        let semaphore = semaphore.clone();
+        let client = client.clone();
+        let db_pool = db_pool.clone();
+        let embed_url = embed_url.clone();
+        let vector_url = vector_url.clone();
+        let document_id = job.document_id;
+        let job_id = job.id;
+        let filename = job.filename.clone();
+        let user_id = job.user_id.clone();
+
+        handles.push(tokio::spawn(async move {
+            let _permit = semaphore.acquire_owned().await.expect("ingestion semaphore closed");
+            process_chunk(&client, &embed_url, &vector_url, &db_pool, document_id, job_id, &filename, &user_id, chunk).await
+        }));
+    }
+
+    let mut failed_count = 0i32;
+    for handle in handles {
+        match handle.await {
+            Ok((_ok, failed)) => {
+                failed_count += failed;
+            }
+            Err(e) => {
+                error!("Ingestion chunk task for job {} panicked: {}", job.id, e);
+            }
+        }
+    }
+
+    // Report `passages_done`/`passages_failed` as reconciled against the
+    // `passages` table (the source of truth `already_indexed` reads from)
+    // rather than accumulating this attempt's counts onto the previous
+    // attempt's: a passage that failed on an earlier job attempt and
+    // succeeded on this one would otherwise be counted as both done and
+    // permanently failed forever, since nothing ever undoes the earlier
+    // attempt's `passages_failed` increment.
+    let done_count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM passages WHERE doc_id = $1")
+        .bind(job.document_id)
+        .fetch_one(db_pool)
+        .await
+        .unwrap_or(0) as i32;
+    let outstanding_count = (passages.len() as i32 - done_count).max(0);
+
+    sqlx::query(
+        "UPDATE ingestion_jobs SET passages_done = $1, passages_failed = $2, updated_at = NOW() WHERE id = $3",
+    )
+    .bind(done_count)
+    .bind(outstanding_count)
+    .bind(job.id)
+    .execute(db_pool)
+    .await?;
+
+    if failed_count > 0 {
+        return Err(anyhow::anyhow!(
+            "{} of {} remaining passages failed to embed/index after {} attempts each",
+            failed_count,
+            remaining.len(),
+            MAX_CHUNK_ATTEMPTS
+        ));
+    }
+
+    Ok(())
+}
+
+/// Embeds, indexes, and persists one chunk, retrying the whole chunk up to
+/// `MAX_CHUNK_ATTEMPTS` times with jittered exponential backoff before
+/// giving up on it. Returns `(indexed_count, failed_count)` for this chunk
+/// rather than propagating an error, so one permanently-failing chunk
+/// doesn't stop its siblings or lose their results.
+#[allow(clippy::too_many_arguments)]
+async fn process_chunk(
+    client: &reqwest::Client,
+    embed_url: &str,
+    vector_url: &str,
+    db_pool: &DbPool,
+    document_id: Uuid,
+    job_id: Uuid,
+    filename: &str,
+    user_id: &str,
+    chunk: Vec<(usize, SanitizedPassage, Option<i64>)>,
+) -> (i32, i32) {
+    let mut last_error = String::new();
+
+    for attempt in 1..=MAX_CHUNK_ATTEMPTS {
+        match try_index_chunk(client, embed_url, vector_url, db_pool, document_id, filename, user_id, &chunk).await {
+            Ok(()) => return (chunk.len() as i32, 0),
+            Err(e) => {
+                warn!(
+                    "Embed/index chunk for document {} attempt {}/{} failed: {}",
+                    document_id, attempt, MAX_CHUNK_ATTEMPTS, e
+                );
+                last_error = e;
+                if attempt < MAX_CHUNK_ATTEMPTS {
+                    let jitter_ms = rand::thread_rng().gen_range(0..250);
+                    let backoff = Duration::from_millis(200 * 2u64.pow(attempt - 1) + jitter_ms);
+                    tokio::time::sleep(backoff).await;
+                }
+            }
+        }
+    }
+
+    error!(
+        "Chunk of {} passages for document {} exhausted retries and was dropped: {}",
+        chunk.len(), document_id, last_error
+    );
+    let _ = sqlx::query(
+        "INSERT INTO audit_log (id, request_id, task_id, event_type, actor, timestamp, details) \
+         VALUES ($1, NULL, NULL, 'ingestion_chunk_failed', 'ingestion_worker', NOW(), $2)",
+    )
+    .bind(Uuid::new_v4())
+    .bind(json!({
+        "job_id": job_id,
+        "document_id": document_id,
+        "passage_indices": chunk.iter().map(|(index, _, _)| *index).collect::<Vec<_>>(),
+        "error": last_error,
+    }))
+    .execute(db_pool)
+    .await;
+
+    (0, chunk.len() as i32)
+}
+
+async fn try_index_chunk(
+    client: &reqwest::Client,
+    embed_url: &str,
+    vector_url: &str,
+    db_pool: &DbPool,
+    document_id: Uuid,
+    filename: &str,
+    user_id: &str,
+    chunk: &[(usize, SanitizedPassage, Option<i64>)],
+) -> Result<(), String> {
+    let chunk_texts: Vec<&str> = chunk.iter().map(|(_, sanitized, _)| sanitized.text.as_str()).collect();
+    let embed_resp: Value = client
+        .post(format!("{}/embed", embed_url))
+        .json(&json!({ "texts": chunk_texts }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+    let embeddings = embed_resp["embeddings"].as_array().cloned().unwrap_or_default();
+
+    let chunk_metas: Vec<Value> = chunk
+        .iter()
+        .map(|(index, sanitized, page)| {
+            json!({
+                "text": sanitized.text,
+                "doc_id": document_id.to_string(),
+                "page": page,
+                "filename": filename,
+                "user_id": user_id,
+                "passage_index": index,
+                "suspicious": sanitized.suspicious,
+                "risk_score": sanitized.risk_score,
+                "matched_patterns": sanitized.matched_patterns,
+            })
+        })
+        .collect();
+
+    client
+        .post(format!("{}/index/add", vector_url))
+        .json(&json!({ "vectors": embeddings, "metadata": chunk_metas }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    for (index, sanitized, page) in chunk {
+        // `ON CONFLICT DO NOTHING` on `(doc_id, passage_index)` makes this
+        // idempotent: if an earlier insert in this chunk succeeds and a
+        // later one fails, the whole chunk (including `/index/add` above)
+        // is retried from `process_chunk`, and without this the earlier
+        // passages would be duplicated rather than skipped.
+        sqlx::query(
+            "INSERT INTO passages (id, doc_id, passage_index, text, char_start, char_end, page_num, metadata) \
+             VALUES ($1, $2, $3, $4, 0, 0, $5, $6) \
+             ON CONFLICT (doc_id, passage_index) DO NOTHING",
+        )
+        .bind(Uuid::new_v4())
+        .bind(document_id)
+        .bind(*index as i32)
+        .bind(&sanitized.text)
+        .bind(page.map(|page| page as i32))
+        .bind(json!({
+            "suspicious": sanitized.suspicious,
+            "risk_score": sanitized.risk_score,
+            "matched_patterns": sanitized.matched_patterns,
+        }))
+        .execute(db_pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
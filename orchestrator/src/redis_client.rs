@@ -6,13 +6,14 @@ use serde::{Serialize, Deserialize};
 #[derive(Clone)]
 pub struct RedisClient {
     connection: ConnectionManager,
+    client: redis::Client,
 }
 
 impl RedisClient {
     pub async fn new(redis_url: &str) -> Result<Self> {
         let client = redis::Client::open(redis_url)?;
         let connection = client.get_tokio_connection_manager().await?;
-        Ok(Self { connection })
+        Ok(Self { connection, client })
     }
 
     pub async fn publish(&mut self, channel: &str, message: &str) -> Result<()> {
@@ -21,6 +22,15 @@ impl RedisClient {
         Ok(())
     }
 
+    /// Opens a dedicated pub/sub connection subscribed to `channel`. A
+    /// separate connection from `self.connection` is required because
+    /// subscribing hands the connection over to pub/sub mode in redis-rs.
+    pub async fn subscribe(&self, channel: &str) -> Result<redis::aio::PubSub> {
+        let mut pubsub = self.client.get_async_connection().await?.into_pubsub();
+        pubsub.subscribe(channel).await?;
+        Ok(pubsub)
+    }
+
     pub async fn set_with_expiry<T: Serialize>(
         &mut self,
         key: &str,
@@ -0,0 +1,61 @@
+use super::{ByteRange, Store, StoredObject};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::path::PathBuf;
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, SeekFrom};
+
+pub struct FilesystemStore {
+    base_dir: PathBuf,
+}
+
+impl FilesystemStore {
+    pub fn new(base_dir: String) -> Self {
+        Self { base_dir: PathBuf::from(base_dir) }
+    }
+
+    fn path_for(&self, key: &str) -> PathBuf {
+        self.base_dir.join(key)
+    }
+}
+
+#[async_trait]
+impl Store for FilesystemStore {
+    async fn put(&self, key: &str, bytes: Vec<u8>, _content_type: &str) -> Result<()> {
+        let path = self.path_for(key);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await.context("creating storage directory")?;
+        }
+        fs::write(&path, &bytes).await.context("writing file to storage")?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str, range: Option<ByteRange>) -> Result<StoredObject> {
+        let path = self.path_for(key);
+        let mut file = fs::File::open(&path).await.context("opening stored file")?;
+        let total_len = file.metadata().await.context("reading stored file metadata")?.len();
+
+        let bytes = match range {
+            Some((start, end)) => {
+                let end = end.unwrap_or(total_len.saturating_sub(1)).min(total_len.saturating_sub(1));
+                let len = (end.saturating_sub(start) + 1) as usize;
+                file.seek(SeekFrom::Start(start)).await.context("seeking stored file")?;
+                let mut buf = vec![0u8; len];
+                file.read_exact(&mut buf).await.context("reading stored file range")?;
+                buf
+            }
+            None => {
+                let mut buf = Vec::new();
+                file.read_to_end(&mut buf).await.context("reading stored file")?;
+                buf
+            }
+        };
+
+        Ok(StoredObject { bytes, content_type: "application/octet-stream".to_string(), total_len })
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        fs::remove_file(self.path_for(key)).await.context("deleting stored file")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+// Pluggable document storage: `Store` abstracts over where original
+// uploaded files live so a deployment can run on local disk by default and
+// swap to S3 purely through config, mirroring how `LlmRegistry` abstracts
+// over LLM providers.
+
+pub mod filesystem;
+pub mod s3;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// An inclusive byte range: `(start, None)` means "from `start` to EOF",
+/// mirroring an HTTP `Range: bytes=start-` header.
+pub type ByteRange = (u64, Option<u64>);
+
+pub struct StoredObject {
+    pub bytes: Vec<u8>,
+    pub content_type: String,
+    pub total_len: u64,
+}
+
+#[async_trait]
+pub trait Store: Send + Sync {
+    async fn put(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<()>;
+    async fn get(&self, key: &str, range: Option<ByteRange>) -> Result<StoredObject>;
+    async fn delete(&self, key: &str) -> Result<()>;
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StorageConfig {
+    Filesystem {
+        base_dir: String,
+    },
+    S3 {
+        bucket: String,
+        region: String,
+        endpoint: String,
+        access_key: String,
+        secret_key: String,
+    },
+}
+
+impl StorageConfig {
+    pub fn build(&self) -> Result<Arc<dyn Store>> {
+        match self {
+            StorageConfig::Filesystem { base_dir } => {
+                Ok(Arc::new(filesystem::FilesystemStore::new(base_dir.clone())))
+            }
+            StorageConfig::S3 { bucket, region, endpoint, access_key, secret_key } => Ok(Arc::new(
+                s3::S3Store::new(bucket.clone(), region.clone(), endpoint.clone(), access_key.clone(), secret_key.clone())?,
+            )),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use super::{ByteRange, Store, StoredObject};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use rusty_s3::{Bucket, Credentials, S3Action, UrlStyle};
+use std::time::Duration;
+
+/// Presigned URLs are generated per-request and only need to survive the
+/// single upload/download round trip they're used for.
+const PRESIGN_TTL: Duration = Duration::from_secs(60);
+
+pub struct S3Store {
+    bucket: Bucket,
+    credentials: Credentials,
+    client: reqwest::Client,
+}
+
+impl S3Store {
+    pub fn new(
+        bucket: String,
+        region: String,
+        endpoint: String,
+        access_key: String,
+        secret_key: String,
+    ) -> Result<Self> {
+        let endpoint_url = endpoint.parse().map_err(|e| anyhow!("invalid S3 endpoint '{}': {}", endpoint, e))?;
+        let bucket = Bucket::new(endpoint_url, UrlStyle::Path, bucket, region)
+            .map_err(|e| anyhow!("invalid S3 bucket config: {}", e))?;
+        let credentials = Credentials::new(access_key, secret_key);
+        Ok(Self { bucket, credentials, client: reqwest::Client::new() })
+    }
+}
+
+#[async_trait]
+impl Store for S3Store {
+    async fn put(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<()> {
+        let action = self.bucket.put_object(Some(&self.credentials), key);
+        let url = action.sign(PRESIGN_TTL);
+        self.client
+            .put(url)
+            .header("Content-Type", content_type)
+            .body(bytes)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str, range: Option<ByteRange>) -> Result<StoredObject> {
+        let action = self.bucket.get_object(Some(&self.credentials), key);
+        let url = action.sign(PRESIGN_TTL);
+
+        let mut req = self.client.get(url);
+        if let Some((start, end)) = range {
+            let header = match end {
+                Some(end) => format!("bytes={}-{}", start, end),
+                None => format!("bytes={}-", start),
+            };
+            req = req.header("Range", header);
+        }
+
+        let resp = req.send().await?.error_for_status()?;
+        let content_type = resp
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("application/octet-stream")
+            .to_string();
+        let total_len = resp
+            .headers()
+            .get("content-range")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit('/').next())
+            .and_then(|v| v.parse().ok())
+            .or_else(|| resp.content_length())
+            .unwrap_or(0);
+        let bytes = resp.bytes().await?.to_vec();
+
+        Ok(StoredObject { bytes, content_type, total_len })
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let action = self.bucket.delete_object(Some(&self.credentials), key);
+        let url = action.sign(PRESIGN_TTL);
+        self.client.delete(url).send().await?.error_for_status()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+// In-process fan-out layer bridging a per-request Redis pub/sub token
+// stream to one or more SSE clients. Subscribing directly to Redis per
+// client would mean a client that connects after generation has started
+// (or finished) misses everything published before it joined, since Redis
+// pub/sub has no replay. `StreamHub` keeps a small buffer per request so a
+// late joiner still gets the events it missed, then an LRU bound evicts the
+// requests least recently published to or subscribed to, whether or not
+// they have finished, so memory doesn't grow unbounded.
+
+use crate::models::Citation;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::sync::{broadcast, Mutex};
+use uuid::Uuid;
+
+const LRU_CAPACITY: usize = 200;
+const BROADCAST_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    Token { text: String },
+    Citations { citations: Vec<Citation> },
+    Done,
+    Error { message: String },
+}
+
+impl StreamEvent {
+    /// True once the request's output is fully delivered (or failed), so
+    /// the Redis relay knows the request is finished.
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, StreamEvent::Done | StreamEvent::Error { .. })
+    }
+}
+
+struct RequestChannel {
+    sender: broadcast::Sender<StreamEvent>,
+    buffered: Vec<StreamEvent>,
+}
+
+pub struct StreamHub {
+    channels: Mutex<HashMap<Uuid, RequestChannel>>,
+    lru_order: Mutex<VecDeque<Uuid>>,
+}
+
+impl StreamHub {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self { channels: Mutex::new(HashMap::new()), lru_order: Mutex::new(VecDeque::new()) })
+    }
+
+    /// Records `event` in the request's buffer (for late joiners) and fans
+    /// it out to every currently-subscribed client.
+    pub async fn publish(&self, request_id: Uuid, event: StreamEvent) {
+        {
+            let mut channels = self.channels.lock().await;
+            let entry = channels.entry(request_id).or_insert_with(new_channel);
+            entry.buffered.push(event.clone());
+            let _ = entry.sender.send(event);
+        }
+        self.touch_lru(request_id).await;
+    }
+
+    /// Subscribes a client to `request_id`, returning everything already
+    /// buffered (in order) plus a receiver for events published from now on.
+    pub async fn subscribe(&self, request_id: Uuid) -> (Vec<StreamEvent>, broadcast::Receiver<StreamEvent>) {
+        let subscription = {
+            let mut channels = self.channels.lock().await;
+            let entry = channels.entry(request_id).or_insert_with(new_channel);
+            (entry.buffered.clone(), entry.sender.subscribe())
+        };
+        self.touch_lru(request_id).await;
+        subscription
+    }
+
+    async fn touch_lru(&self, request_id: Uuid) {
+        let mut order = self.lru_order.lock().await;
+        order.retain(|id| *id != request_id);
+        order.push_back(request_id);
+        if order.len() > LRU_CAPACITY {
+            if let Some(evicted) = order.pop_front() {
+                self.channels.lock().await.remove(&evicted);
+            }
+        }
+    }
+}
+
+fn new_channel() -> RequestChannel {
+    let (sender, _) = broadcast::channel(BROADCAST_CAPACITY);
+    RequestChannel { sender, buffered: Vec::new() }
+}
@@ -0,0 +1,253 @@
+// WebAuthn registration + challenge/assertion verification for
+// human-in-the-loop action approvals. Replaces the unverified
+// `user_signature` string with a platform-authenticator signature over a
+// challenge the server binds to one specific `PendingAction`, so approving
+// action A can never be replayed to approve action B (or a tampered payload
+// for the same action).
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::Row;
+use uuid::Uuid;
+use webauthn_rs::prelude::*;
+
+use crate::api::actions::PendingActionDTO;
+use crate::db::DbPool;
+
+const REGISTRATION_CHALLENGE: &str = "registration";
+const APPROVAL_CHALLENGE: &str = "approval";
+
+pub struct WebauthnCtx {
+    webauthn: Webauthn,
+}
+
+/// Outcome of a verified approval assertion: the authenticator proved intent
+/// over `payload_hash`, and the caller still needs to check that hash
+/// against the `PendingAction` row it's about to execute.
+pub struct VerifiedApproval {
+    pub user_id: String,
+    pub payload_hash: String,
+    pub credential_id: String,
+    pub counter: u32,
+}
+
+impl WebauthnCtx {
+    /// Built once at startup from `WEBAUTHN_RP_ID`/`WEBAUTHN_RP_ORIGIN`
+    /// (falling back to values suitable for local development), mirroring
+    /// how `RedisClient`/`LlmRegistry` are constructed from env at boot.
+    pub fn new() -> Result<Self> {
+        let rp_id = std::env::var("WEBAUTHN_RP_ID").unwrap_or_else(|_| "localhost".to_string());
+        let rp_origin_raw =
+            std::env::var("WEBAUTHN_RP_ORIGIN").unwrap_or_else(|_| "http://localhost:8080".to_string());
+        let rp_origin = Url::parse(&rp_origin_raw)
+            .map_err(|e| anyhow!("invalid WEBAUTHN_RP_ORIGIN '{}': {}", rp_origin_raw, e))?;
+        let rp_name = std::env::var("WEBAUTHN_RP_NAME").unwrap_or_else(|_| "Agentic AI Orchestrator".to_string());
+
+        let webauthn = WebauthnBuilder::new(&rp_id, &rp_origin)?
+            .rp_name(&rp_name)
+            .build()?;
+
+        Ok(Self { webauthn })
+    }
+
+    /// Step 1 of registering a platform authenticator for `user_id`: issues
+    /// a creation challenge and stashes the matching `PasskeyRegistration`
+    /// state (needed by `finish_registration`) keyed on the challenge id.
+    pub async fn start_registration(
+        &self,
+        db_pool: &DbPool,
+        user_id: &str,
+    ) -> Result<(Uuid, CreationChallengeResponse)> {
+        let existing = self.user_credentials(db_pool, user_id).await?;
+        let exclude_ids: Vec<CredentialID> = existing.iter().map(|pk| pk.cred_id().clone()).collect();
+
+        let user_unique_id = user_handle(user_id);
+        let (challenge, state) = self.webauthn.start_passkey_registration(
+            user_unique_id,
+            user_id,
+            user_id,
+            Some(exclude_ids),
+        )?;
+
+        let challenge_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO webauthn_challenges (id, user_id, pending_action_id, challenge_type, state) \
+             VALUES ($1, $2, NULL, $3, $4)",
+        )
+        .bind(challenge_id)
+        .bind(user_id)
+        .bind(REGISTRATION_CHALLENGE)
+        .bind(serde_json::to_value(&state)?)
+        .execute(db_pool)
+        .await?;
+
+        Ok((challenge_id, challenge))
+    }
+
+    /// Step 2: verifies the browser's attestation against the stashed state
+    /// and persists the resulting passkey as the user's credential.
+    pub async fn finish_registration(
+        &self,
+        db_pool: &DbPool,
+        challenge_id: Uuid,
+        credential: &RegisterPublicKeyCredential,
+    ) -> Result<()> {
+        let row = sqlx::query(
+            "SELECT user_id, state FROM webauthn_challenges \
+             WHERE id = $1 AND challenge_type = $2 AND consumed_at IS NULL",
+        )
+        .bind(challenge_id)
+        .bind(REGISTRATION_CHALLENGE)
+        .fetch_optional(db_pool)
+        .await?
+        .ok_or_else(|| anyhow!("unknown or already-consumed registration challenge"))?;
+
+        let user_id: String = row.try_get("user_id")?;
+        let state: PasskeyRegistration = serde_json::from_value(row.try_get("state")?)?;
+
+        let passkey = self.webauthn.finish_passkey_registration(credential, &state)?;
+
+        sqlx::query(
+            "INSERT INTO webauthn_credentials (id, user_id, credential_id, passkey) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(Uuid::new_v4())
+        .bind(&user_id)
+        .bind(passkey.cred_id().as_ref())
+        .bind(serde_json::to_value(&passkey)?)
+        .execute(db_pool)
+        .await?;
+
+        mark_consumed(db_pool, challenge_id).await?;
+        Ok(())
+    }
+
+    /// Issues the challenge a client must sign with their registered
+    /// authenticator in order to approve `action`. The challenge is stored
+    /// bound to both `action.id` and a hash of its current payload, so a
+    /// payload that changes between challenge issuance and verification
+    /// fails the hash check in `verify_approval` below.
+    pub async fn start_approval_challenge(
+        &self,
+        db_pool: &DbPool,
+        user_id: &str,
+        action: &PendingActionDTO,
+    ) -> Result<(Uuid, RequestChallengeResponse)> {
+        let credentials = self.user_credentials(db_pool, user_id).await?;
+        if credentials.is_empty() {
+            return Err(anyhow!("user '{}' has no registered WebAuthn credential", user_id));
+        }
+
+        let (challenge, state) = self.webauthn.start_passkey_authentication(&credentials)?;
+        let payload_hash = hash_payload(action.id, &action.payload);
+
+        let challenge_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO webauthn_challenges (id, user_id, pending_action_id, challenge_type, state, payload_hash) \
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(challenge_id)
+        .bind(user_id)
+        .bind(action.id)
+        .bind(APPROVAL_CHALLENGE)
+        .bind(serde_json::to_value(&state)?)
+        .bind(&payload_hash)
+        .execute(db_pool)
+        .await?;
+
+        Ok((challenge_id, challenge))
+    }
+
+    /// Verifies the signed assertion against the stashed authentication
+    /// state, rejects a regressed signature counter (a classic sign of a
+    /// cloned authenticator), and returns the bound `payload_hash` for the
+    /// caller to compare against the action it's about to execute.
+    pub async fn verify_approval(
+        &self,
+        db_pool: &DbPool,
+        challenge_id: Uuid,
+        assertion: &PublicKeyCredential,
+    ) -> Result<VerifiedApproval> {
+        let row = sqlx::query(
+            "SELECT user_id, state, payload_hash FROM webauthn_challenges \
+             WHERE id = $1 AND challenge_type = $2 AND consumed_at IS NULL",
+        )
+        .bind(challenge_id)
+        .bind(APPROVAL_CHALLENGE)
+        .fetch_optional(db_pool)
+        .await?
+        .ok_or_else(|| anyhow!("unknown or already-consumed approval challenge"))?;
+
+        let user_id: String = row.try_get("user_id")?;
+        let payload_hash: String = row.try_get("payload_hash")?;
+        let state: PasskeyAuthentication = serde_json::from_value(row.try_get("state")?)?;
+
+        let result = self.webauthn.finish_passkey_authentication(assertion, &state)?;
+
+        let existing_row = sqlx::query(
+            "SELECT id, passkey FROM webauthn_credentials WHERE credential_id = $1",
+        )
+        .bind(result.cred_id().as_ref())
+        .fetch_optional(db_pool)
+        .await?
+        .ok_or_else(|| anyhow!("assertion signed by an unrecognized credential"))?;
+
+        let mut passkey: Passkey = serde_json::from_value(existing_row.try_get("passkey")?)?;
+        if passkey.update_credential(&result).is_none() {
+            return Err(anyhow!(
+                "signature counter did not advance — possible cloned authenticator"
+            ));
+        }
+
+        let credential_row_id: Uuid = existing_row.try_get("id")?;
+        sqlx::query("UPDATE webauthn_credentials SET passkey = $1 WHERE id = $2")
+            .bind(serde_json::to_value(&passkey)?)
+            .bind(credential_row_id)
+            .execute(db_pool)
+            .await?;
+
+        mark_consumed(db_pool, challenge_id).await?;
+
+        Ok(VerifiedApproval {
+            user_id,
+            payload_hash,
+            credential_id: hex::encode(result.cred_id()),
+            counter: result.counter(),
+        })
+    }
+
+    async fn user_credentials(&self, db_pool: &DbPool, user_id: &str) -> Result<Vec<Passkey>> {
+        let rows = sqlx::query("SELECT passkey FROM webauthn_credentials WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_all(db_pool)
+            .await?;
+
+        rows.into_iter()
+            .map(|row| Ok(serde_json::from_value(row.try_get("passkey")?)?))
+            .collect()
+    }
+}
+
+/// Derives a stable WebAuthn user handle from the app's string `user_id`,
+/// since there's no dedicated users table to hand out real UUIDs from.
+fn user_handle(user_id: &str) -> Uuid {
+    Uuid::new_v5(&Uuid::NAMESPACE_OID, user_id.as_bytes())
+}
+
+/// Hashes the fields that matter for approval intent: which action, and
+/// exactly what it will do if executed. Recomputed at verification time so a
+/// payload edited after the challenge was issued fails the comparison.
+pub fn hash_payload(action_id: Uuid, payload: &Value) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(action_id.as_bytes());
+    hasher.update(payload.to_string().as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+async fn mark_consumed(db_pool: &DbPool, challenge_id: Uuid) -> Result<()> {
+    sqlx::query("UPDATE webauthn_challenges SET consumed_at = NOW() WHERE id = $1")
+        .bind(challenge_id)
+        .execute(db_pool)
+        .await?;
+    Ok(())
+}
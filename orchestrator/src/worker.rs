@@ -0,0 +1,80 @@
+// Crash-recovery worker: re-drains `pending_actions` rows that were
+// approved but never reached `execution_status = 'executed'` (e.g. the
+// process died mid-dispatch), so execution stays crash-safe across restarts.
+
+use crate::api::actions::{execute_action, PendingActionDTO};
+use crate::db::DbPool;
+use sqlx::FromRow;
+use std::time::Duration;
+use tracing::{error, info};
+use uuid::Uuid;
+
+const DRAIN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long `execution_status = 'executing'` is trusted to mean "a dispatch
+/// is genuinely in flight" before the recovery worker treats it as
+/// abandoned (the process that set it crashed mid-call) and re-drains it.
+/// Comfortably longer than `execute_with_retries`'s worst case
+/// (`MAX_EXECUTION_ATTEMPTS` attempts with exponential backoff), so a
+/// dispatch that's still legitimately running is never double-executed;
+/// `ActionAgent`'s idempotency keys (chunk1-7) make re-draining a truly
+/// stale row safe either way.
+const EXECUTION_LEASE_SECS: i64 = 120;
+
+#[derive(FromRow)]
+struct StuckActionRow {
+    id: Uuid,
+    action_type: String,
+    payload: serde_json::Value,
+    status: String,
+    approved_by: Option<String>,
+}
+
+/// Spawns a background task that periodically looks for `approved` actions
+/// stuck below `MAX_EXECUTION_ATTEMPTS` and retries them via the same
+/// `execute_action` path the approval handler uses.
+pub fn spawn_action_recovery(db_pool: DbPool) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = drain_stuck_actions(&db_pool).await {
+                error!("Action recovery pass failed: {}", e);
+            }
+            tokio::time::sleep(DRAIN_INTERVAL).await;
+        }
+    });
+}
+
+async fn drain_stuck_actions(db_pool: &DbPool) -> Result<(), sqlx::Error> {
+    // A `not_executed` row never started; an `executing` row started but
+    // hasn't been touched in over `EXECUTION_LEASE_SECS` (update these
+    // together: `execute_with_retries` stamps `execution_status_at` on
+    // every transition), so either is safe to re-drain.
+    let stuck = sqlx::query_as::<_, StuckActionRow>(
+        "SELECT id, action_type, payload, status, approved_by FROM pending_actions \
+         WHERE status = 'approved' AND attempts < 3 AND ( \
+             execution_status = 'not_executed' \
+             OR (execution_status = 'executing' AND execution_status_at < NOW() - ($1 * INTERVAL '1 second')) \
+         )"
+    )
+    .bind(EXECUTION_LEASE_SECS)
+    .fetch_all(db_pool)
+    .await?;
+
+    if stuck.is_empty() {
+        return Ok(());
+    }
+    info!("Action recovery: re-draining {} stuck action(s)", stuck.len());
+
+    for row in stuck {
+        let action = PendingActionDTO {
+            id: row.id,
+            action_type: row.action_type,
+            payload: row.payload,
+            status: row.status,
+        };
+        let approved_by = row.approved_by.unwrap_or_default();
+        let _ = execute_action(db_pool, &action, &approved_by).await;
+    }
+
+    Ok(())
+}